@@ -1,11 +1,11 @@
 use {
+    ezrpc::__private::{tower, RwLock},
     std::{
         future::Future,
         pin::Pin,
         sync::Arc,
         task::{Context, Poll},
     },
-    tokio::sync::RwLock,
 };
 
 pub struct Example;
@@ -37,6 +37,18 @@ impl Example {
 pub struct Service(Arc<RwLock<Example>>);
 
 impl Service {
+    pub fn new(instance: Example) -> Self {
+        Self::from_shared(Arc::new(RwLock::new(instance)))
+    }
+
+    pub fn from_shared(shared: Arc<RwLock<Example>>) -> Self {
+        Service(shared)
+    }
+
+    pub fn inner(&self) -> &Arc<RwLock<Example>> {
+        &self.0
+    }
+
     pub async fn name(&mut self) -> String {
         use tower::{Service as _, ServiceExt as _};
 
@@ -77,17 +89,32 @@ impl Service {
     }
 }
 
+impl Clone for Service {
+    fn clone(&self) -> Self {
+        Service(self.0.clone())
+    }
+}
+
+impl Default for Service
+where
+    for<'__ezrpc> Example: Default,
+{
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
 impl tower::Service<Request> for Service {
     type Response = String;
     type Error = EmptyString;
-    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
 
     fn poll_ready(&mut self, context: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         Poll::Ready(Ok(()))
     }
 
     fn call(&mut self, request: Request) -> Self::Future {
-        use futures::FutureExt as _;
+        use ezrpc::__private::futures::FutureExt as _;
 
         let inner = self.0.clone();
 
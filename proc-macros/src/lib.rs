@@ -1,26 +1,43 @@
 mod tower;
 
 use {
-    crate::tower::Generator,
+    crate::tower::{Generator, MacroArguments, MethodAttributes, TraitData},
     proc_macro::TokenStream,
-    proc_macro_error::proc_macro_error,
+    proc_macro_error::{abort, proc_macro_error},
     quote::quote,
-    syn::{parse_macro_input, ItemImpl},
+    syn::{parse_macro_input, Item},
 };
 
 #[proc_macro_error]
 #[proc_macro_attribute]
-pub fn tower(_attribute: TokenStream, item_tokens: TokenStream) -> TokenStream {
-    let item = parse_macro_input!(item_tokens as ItemImpl);
-    let generator = Generator::new(&item);
-    let request = generator.request();
-    let response = generator.response();
-    let service = generator.service();
+pub fn tower(attribute: TokenStream, item_tokens: TokenStream) -> TokenStream {
+    let arguments = parse_macro_input!(attribute as MacroArguments);
+    let mut item = parse_macro_input!(item_tokens as Item);
+
+    let generator = match &mut item {
+        Item::Impl(item_impl) => Generator::new(item_impl, arguments),
+        Item::Trait(item_trait) => {
+            let local = arguments.is_local();
+            let generator = Generator::from_trait(item_trait, arguments);
+
+            if !local {
+                TraitData::make_futures_send(item_trait);
+            }
+
+            generator
+        }
+        _ => abort!(
+            item,
+            "`#[ezrpc::tower]` can only be applied to `impl` blocks or traits"
+        ),
+    };
+
+    let generated_items = generator.generated_items();
+
+    MethodAttributes::strip(&mut item);
 
     TokenStream::from(quote! {
-        #request
-        #response
         #item
-        #service
+        #generated_items
     })
 }
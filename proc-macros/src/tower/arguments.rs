@@ -0,0 +1,433 @@
+use {
+    proc_macro2::{Span, TokenStream},
+    quote::{quote, ToTokens},
+    syn::{
+        ext::IdentExt,
+        parenthesized,
+        parse::{Parse, ParseStream},
+        parse_quote,
+        punctuated::Punctuated,
+        Ident, LitStr, Meta, Path, Token,
+    },
+};
+
+/// The arguments passed to the `#[ezrpc::tower(...)]` attribute.
+pub struct MacroArguments {
+    /// The name of the generated `Request` type.
+    request: Ident,
+
+    /// The name of the generated `Response` type.
+    response: Ident,
+
+    /// The name of the generated `Service` type.
+    service: Ident,
+
+    /// The name of the generated `Client` type.
+    client: Ident,
+
+    /// The name of the module to wrap all generated items in, if any.
+    module: Option<Ident>,
+
+    /// If methods that aren't `pub` should be left out of the RPC interface.
+    skip_private: bool,
+
+    /// If the generated futures are allowed to not be [`Send`].
+    local: bool,
+
+    /// The path to the `ezrpc` crate.
+    crate_path: Path,
+
+    /// How concurrent calls to the methods are handled.
+    concurrency: Concurrency,
+
+    /// The name of the generated `Error` enum, if one should be generated.
+    error_enum: Option<Ident>,
+
+    /// The traits to derive for the generated `Request` and `Response` enums.
+    derives: Vec<Path>,
+
+    /// The extra attributes to place on the generated `Request` and `Response` enums.
+    attributes: Vec<Meta>,
+
+    /// If the generated `Request`, `Response` and `Error` enums implement `Serialize` and
+    /// `Deserialize`.
+    serde: bool,
+
+    /// How the generated `Request` and `Response` enums are tagged when serialized.
+    tagging: Tagging,
+
+    /// The name of the generated `RemoteClient` type, if one should be generated.
+    remote_client: Option<Ident>,
+}
+
+/// How the generated `Service` handles concurrent calls to the methods.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Concurrency {
+    /// The instance is shared between calls, and kept behind a lock if any method requires a
+    /// `&mut self` receiver.
+    Lock,
+
+    /// The instance is moved into an actor task that receives `Request`s through a mailbox and
+    /// executes them serially.
+    Actor,
+}
+
+/// How the variants of the generated `Request` and `Response` enums are represented when
+/// serialized with `serde`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Tagging {
+    /// The variant name is used as the key of an object that contains the variant's data.
+    External,
+
+    /// The variant name is stored in a `method` field next to the `Request` variant's fields.
+    ///
+    /// `Response` variants are not objects, so they are adjacently tagged instead.
+    Internal,
+
+    /// The variant name is stored in a `method` field, and the variant's data is stored in a
+    /// `params` field for `Request`s and an `output` field for `Response`s.
+    Adjacent,
+}
+
+impl Tagging {
+    /// The name of the field that stores the variant name, with internal or adjacent tagging.
+    const TAG: &'static str = "method";
+
+    /// The name of the field that stores a `Request` variant's data, with adjacent tagging.
+    const REQUEST_CONTENT: &'static str = "params";
+
+    /// The name of the field that stores a `Response` variant's data, with internal or adjacent
+    /// tagging.
+    const RESPONSE_CONTENT: &'static str = "output";
+
+    /// Retrieve the names that `Request` variant fields can't use, because they would clash with
+    /// the fields added by the tagging.
+    ///
+    /// Only internal tagging stores the variant's fields next to the tag, so the other tagging
+    /// representations allow any name.
+    pub fn reserved_field_names(self) -> &'static [&'static str] {
+        match self {
+            Tagging::Internal => &[Self::TAG],
+            Tagging::External | Tagging::Adjacent => &[],
+        }
+    }
+}
+
+impl MacroArguments {
+    /// Retrieve the name of the generated `Request` type.
+    pub fn request(&self) -> &Ident {
+        &self.request
+    }
+
+    /// Retrieve the name of the generated `Response` type.
+    pub fn response(&self) -> &Ident {
+        &self.response
+    }
+
+    /// Retrieve the name of the generated `Service` type.
+    pub fn service(&self) -> &Ident {
+        &self.service
+    }
+
+    /// Retrieve the name of the generated `Client` type.
+    pub fn client(&self) -> &Ident {
+        &self.client
+    }
+
+    /// Retrieve the name of the generated `RemoteClient` type, if one should be generated.
+    pub fn remote_client(&self) -> Option<&Ident> {
+        self.remote_client.as_ref()
+    }
+
+    /// Retrieve the name of the module to wrap the generated items in, if one was requested.
+    pub fn module(&self) -> Option<&Ident> {
+        self.module.as_ref()
+    }
+
+    /// Check if methods that aren't `pub` should be left out of the RPC interface.
+    pub fn skip_private(&self) -> bool {
+        self.skip_private
+    }
+
+    /// Check if the generated futures are allowed to not be [`Send`].
+    pub fn is_local(&self) -> bool {
+        self.local
+    }
+
+    /// Retrieve how concurrent calls to the methods are handled.
+    pub fn concurrency(&self) -> Concurrency {
+        self.concurrency
+    }
+
+    /// Retrieve the name of the generated `Error` enum, if one should be generated.
+    pub fn error_enum(&self) -> Option<&Ident> {
+        self.error_enum.as_ref()
+    }
+
+    /// Retrieve how the generated `Request` and `Response` enums are tagged when serialized.
+    pub fn tagging(&self) -> Tagging {
+        self.tagging
+    }
+
+    /// Generate the `#[derive(...)]` and other attributes to place on the generated `Request`
+    /// enum.
+    pub fn request_attributes(&self) -> TokenStream {
+        let tag = Tagging::TAG;
+        let content = Tagging::REQUEST_CONTENT;
+        let tagging = match self.tagging {
+            Tagging::External => quote! {},
+            Tagging::Internal => quote! { tag = #tag },
+            Tagging::Adjacent => quote! { tag = #tag, content = #content },
+        };
+
+        self.enum_attributes(tagging)
+    }
+
+    /// Generate the `#[derive(...)]` and other attributes to place on the generated `Response`
+    /// enum.
+    pub fn response_attributes(&self) -> TokenStream {
+        let tag = Tagging::TAG;
+        let content = Tagging::RESPONSE_CONTENT;
+        let tagging = match self.tagging {
+            Tagging::External => quote! {},
+            Tagging::Internal | Tagging::Adjacent => quote! { tag = #tag, content = #content },
+        };
+
+        self.enum_attributes(tagging)
+    }
+
+    /// Generate the attributes to place on the generated `Error` enum.
+    ///
+    /// If `serde` was requested, the enum derives `Serialize` and `Deserialize`, so that errors
+    /// can be sent with the responses.
+    pub fn error_enum_attributes(&self) -> TokenStream {
+        if self.serde {
+            let private = self.private_module();
+            let serde_path = quote! { #private::serde }.to_string();
+
+            quote! {
+                #[derive(#private::serde::Serialize, #private::serde::Deserialize)]
+                #[serde(crate = #serde_path)]
+            }
+        } else {
+            quote! {}
+        }
+    }
+
+    /// Generate the `#[derive(...)]` and other attributes to place on a generated enum.
+    ///
+    /// If `serde` was requested, the enum also derives `Serialize` and `Deserialize` with the
+    /// `serde_tagging` options.
+    fn enum_attributes(&self, serde_tagging: TokenStream) -> TokenStream {
+        let mut derives: Vec<_> = self.derives.iter().map(ToTokens::to_token_stream).collect();
+        let attributes = &self.attributes;
+        let mut serde_attribute = quote! {};
+
+        if self.serde {
+            let private = self.private_module();
+            let serde_path = quote! { #private::serde }.to_string();
+
+            derives.push(quote! { #private::serde::Serialize });
+            derives.push(quote! { #private::serde::Deserialize });
+            serde_attribute = quote! { #[serde(crate = #serde_path, #serde_tagging)] };
+        }
+
+        let derive_attribute = if derives.is_empty() {
+            quote! {}
+        } else {
+            quote! { #[derive( #( #derives ),* )] }
+        };
+
+        quote! {
+            #derive_attribute
+            #serde_attribute
+            #( #[#attributes] )*
+        }
+    }
+
+    /// Retrieve the path to the `ezrpc` crate.
+    pub fn crate_path(&self) -> &Path {
+        &self.crate_path
+    }
+
+    /// Retrieve the path to the hidden module in the `ezrpc` crate that re-exports the
+    /// dependencies used by the generated code.
+    pub fn private_module(&self) -> TokenStream {
+        let crate_path = &self.crate_path;
+
+        quote! { #crate_path::__private }
+    }
+
+    /// Parse the value of a `name = Identifier` argument.
+    fn parse_identifier_value(input: ParseStream) -> syn::Result<Ident> {
+        input.parse::<Token![=]>()?;
+        input.parse()
+    }
+
+    /// Parse the value of a `concurrency = mode` argument.
+    fn parse_concurrency_value(input: ParseStream) -> syn::Result<Concurrency> {
+        let mode = Self::parse_identifier_value(input)?;
+
+        match mode.to_string().as_str() {
+            "lock" => Ok(Concurrency::Lock),
+            "actor" => Ok(Concurrency::Actor),
+            _ => Err(syn::Error::new(
+                mode.span(),
+                format!("Unknown `{}` concurrency mode", mode),
+            )),
+        }
+    }
+
+    /// Check that the `serde` feature of `ezrpc` is enabled, which the `argument` requires.
+    fn check_serde_feature(argument: &Ident) -> syn::Result<()> {
+        if cfg!(feature = "serde") {
+            Ok(())
+        } else {
+            Err(syn::Error::new(
+                argument.span(),
+                format!(
+                    "The `{}` argument requires the `serde` feature of `ezrpc`",
+                    argument
+                ),
+            ))
+        }
+    }
+
+    /// Parse the value of a `tagging = representation` argument.
+    fn parse_tagging_value(input: ParseStream) -> syn::Result<Tagging> {
+        let tagging = Self::parse_identifier_value(input)?;
+
+        match tagging.to_string().as_str() {
+            "external" => Ok(Tagging::External),
+            "internal" => Ok(Tagging::Internal),
+            "adjacent" => Ok(Tagging::Adjacent),
+            _ => Err(syn::Error::new(
+                tagging.span(),
+                format!("Unknown `{}` tagging", tagging),
+            )),
+        }
+    }
+
+    /// Parse the comma-separated list inside a `name(...)` argument.
+    fn parse_list_value<T: Parse>(input: ParseStream) -> syn::Result<Vec<T>> {
+        let content;
+
+        parenthesized!(content in input);
+
+        let list = Punctuated::<T, Token![,]>::parse_terminated(&content)?;
+
+        Ok(list.into_iter().collect())
+    }
+
+    /// Parse the value of a `name = "path"` argument.
+    fn parse_path_value(input: ParseStream) -> syn::Result<Path> {
+        input.parse::<Token![=]>()?;
+
+        let path: LitStr = input.parse()?;
+
+        path.parse()
+    }
+}
+
+impl Default for MacroArguments {
+    fn default() -> Self {
+        MacroArguments {
+            request: Ident::new("Request", Span::call_site()),
+            response: Ident::new("Response", Span::call_site()),
+            service: Ident::new("Service", Span::call_site()),
+            client: Ident::new("Client", Span::call_site()),
+            module: None,
+            skip_private: false,
+            local: false,
+            crate_path: parse_quote! { ::ezrpc },
+            concurrency: Concurrency::Lock,
+            error_enum: None,
+            derives: Vec::new(),
+            attributes: Vec::new(),
+            serde: false,
+            tagging: Tagging::External,
+            remote_client: None,
+        }
+    }
+}
+
+impl Parse for MacroArguments {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut arguments = MacroArguments::default();
+        let mut parsed_names: Vec<Ident> = Vec::new();
+
+        while !input.is_empty() {
+            let name = if input.peek(Token![?]) {
+                input.parse::<Token![?]>()?;
+
+                let send: Ident = input.parse()?;
+
+                if send != "Send" {
+                    return Err(syn::Error::new(send.span(), "Expected `?Send`"));
+                }
+
+                Ident::new("local", send.span())
+            } else {
+                input.call(Ident::parse_any)?
+            };
+
+            if parsed_names.contains(&name) {
+                return Err(syn::Error::new(
+                    name.span(),
+                    format!("Duplicate `{}` argument", name),
+                ));
+            }
+
+            match name.to_string().as_str() {
+                "request" => arguments.request = Self::parse_identifier_value(input)?,
+                "response" => arguments.response = Self::parse_identifier_value(input)?,
+                "service" => arguments.service = Self::parse_identifier_value(input)?,
+                "client" => arguments.client = Self::parse_identifier_value(input)?,
+                "module" => arguments.module = Some(Self::parse_identifier_value(input)?),
+                "skip_private" => arguments.skip_private = true,
+                "local" => arguments.local = true,
+                "crate" => arguments.crate_path = Self::parse_path_value(input)?,
+                "concurrency" => arguments.concurrency = Self::parse_concurrency_value(input)?,
+                "error_enum" => {
+                    arguments.error_enum = Some(if input.peek(Token![=]) {
+                        Self::parse_identifier_value(input)?
+                    } else {
+                        Ident::new("Error", name.span())
+                    })
+                }
+                "remote_client" => {
+                    arguments.remote_client = Some(if input.peek(Token![=]) {
+                        Self::parse_identifier_value(input)?
+                    } else {
+                        Ident::new("RemoteClient", name.span())
+                    })
+                }
+                "derive" => arguments.derives = Self::parse_list_value(input)?,
+                "attr" => arguments.attributes = Self::parse_list_value(input)?,
+                "serde" => {
+                    Self::check_serde_feature(&name)?;
+                    arguments.serde = true;
+                }
+                "tagging" => {
+                    Self::check_serde_feature(&name)?;
+                    arguments.serde = true;
+                    arguments.tagging = Self::parse_tagging_value(input)?;
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        format!("Unknown `{}` argument", name),
+                    ))
+                }
+            }
+
+            parsed_names.push(name);
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(arguments)
+    }
+}
@@ -0,0 +1,107 @@
+use {
+    proc_macro2::TokenStream,
+    quote::quote,
+    syn::{parse_quote_spanned, spanned::Spanned, Path, Type, TypeReference},
+};
+
+/// Representation of a borrowed type that is sent as its owned counterpart.
+///
+/// References can't be stored in the `Request` and `Response` types, so the referenced value is
+/// converted into an owned value before it is sent, and borrowed back before it is used.
+pub struct BorrowedType {
+    /// The type behind the reference.
+    borrowed_type: Type,
+
+    /// The owned type used to send the value.
+    owned_type: Type,
+}
+
+impl BorrowedType {
+    /// Create a new [`BorrowedType`] from a [`TypeReference`].
+    ///
+    /// String slices, slices and [`std::path::Path`]s are mapped to [`String`], [`Vec`] and
+    /// [`PathBuf`][std::path::PathBuf] respectively. Other types, including user types named
+    /// `Path` or `str`, are mapped to their [`ToOwned::Owned`] type.
+    pub fn new(reference: &TypeReference) -> Self {
+        let borrowed_type = reference.elem.as_ref().clone();
+        let owned_type = Self::owned_type_for(&borrowed_type);
+
+        BorrowedType {
+            borrowed_type,
+            owned_type,
+        }
+    }
+
+    /// Determine the owned type for a `borrowed_type`.
+    ///
+    /// The owned type has the span of the `borrowed_type`, so that errors about it point to the
+    /// original parameter.
+    fn owned_type_for(borrowed_type: &Type) -> Type {
+        let span = borrowed_type.span();
+
+        match borrowed_type {
+            Type::Slice(slice) => {
+                let element_type = &slice.elem;
+
+                parse_quote_spanned! {span=> std::vec::Vec<#element_type> }
+            }
+            Type::Path(path_type) if path_type.qself.is_none() => {
+                if Self::is_std_type(&path_type.path, &["std", "primitive", "str"]) {
+                    parse_quote_spanned! {span=> std::string::String }
+                } else if Self::is_std_type(&path_type.path, &["std", "path", "Path"]) {
+                    parse_quote_spanned! {span=> std::path::PathBuf }
+                } else {
+                    Self::to_owned_type(borrowed_type)
+                }
+            }
+            _ => Self::to_owned_type(borrowed_type),
+        }
+    }
+
+    /// Check if a `path` refers to the standard library type at `std_path`.
+    ///
+    /// The type may be named by its full path, with or without a leading `::`, or just by its
+    /// name. Any other path is assumed to refer to a different type with the same name.
+    fn is_std_type(path: &Path, std_path: &[&str]) -> bool {
+        let segments_match =
+            |segments: &[&str]| {
+                path.segments.len() == segments.len()
+                    && path.segments.iter().zip(segments).all(|(segment, name)| {
+                        segment.ident == name && segment.arguments.is_empty()
+                    })
+            };
+
+        let type_name = &std_path[std_path.len() - 1..];
+
+        segments_match(std_path) || (path.leading_colon.is_none() && segments_match(type_name))
+    }
+
+    /// Build the [`ToOwned::Owned`] type for a `borrowed_type`.
+    fn to_owned_type(borrowed_type: &Type) -> Type {
+        parse_quote_spanned! {borrowed_type.span()=>
+            <#borrowed_type as std::borrow::ToOwned>::Owned
+        }
+    }
+
+    /// Retrieve the owned type used to send the value.
+    pub fn owned_type(&self) -> &Type {
+        &self.owned_type
+    }
+
+    /// Generate the code to convert an `expression` that results in a reference into the owned
+    /// type.
+    pub fn to_owned(expression: TokenStream) -> TokenStream {
+        quote! { std::borrow::ToOwned::to_owned(#expression) }
+    }
+
+    /// Generate the code to borrow the referenced type from an `expression` that results in the
+    /// owned type.
+    pub fn borrow(&self, expression: TokenStream) -> TokenStream {
+        let borrowed_type = &self.borrowed_type;
+        let owned_type = &self.owned_type;
+
+        quote! {
+            <#owned_type as std::borrow::Borrow<#borrowed_type>>::borrow(&#expression)
+        }
+    }
+}
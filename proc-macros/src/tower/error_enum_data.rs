@@ -0,0 +1,265 @@
+use {
+    super::{generics_data::GenericsData, method_data::MethodData},
+    heck::CamelCase,
+    proc_macro2::{Span, TokenStream},
+    proc_macro_error::{abort, emit_error},
+    quote::quote,
+    syn::{parse_quote, Generics, Ident, Type, TypePath},
+};
+
+/// Representation of the generated `Error` enum.
+///
+/// The enum has one variant for each distinct error type returned by the methods, and is used as
+/// the error type of the generated `Service`.
+pub struct ErrorEnumData {
+    /// The name of the generated `Error` enum.
+    name: Ident,
+
+    /// The variants of the enum, with the error type that each variant wraps.
+    variants: Vec<(Ident, Type)>,
+}
+
+impl ErrorEnumData {
+    /// Create a new [`ErrorEnumData`] named `name` from the list of RPC methods.
+    ///
+    /// Each method that returns a [`Result`] is assigned the variant that wraps its error type.
+    /// Variants are named after the last segment of the error type's path converted to CamelCase,
+    /// with a numeric suffix added if necessary to keep the names unique.
+    ///
+    /// Error types are compared by how they are spelled, so the same type must be spelled
+    /// identically in all methods. Aborts if two different spellings may refer to the same type,
+    /// such as `io::Error` and `std::io::Error`, because the generated [`From`] implementations
+    /// would conflict.
+    pub fn new(name: Ident, methods: &mut [MethodData]) -> Self {
+        let mut variants: Vec<(Ident, Type)> = Vec::new();
+        let mut variant_methods: Vec<Ident> = Vec::new();
+
+        for method in methods {
+            let error_type = match method.result().err_type() {
+                Some(error_type) => error_type.clone(),
+                None => continue,
+            };
+
+            if let Some((_, previous_method)) = variants
+                .iter()
+                .zip(&variant_methods)
+                .find(|((_, variant_type), _)| Self::may_alias(variant_type, &error_type))
+            {
+                emit_error!(
+                    previous_method,
+                    "The error type of this method may be the same as the error type of the `{}` \
+                    method, but it is spelled differently",
+                    method.name()
+                );
+                abort!(
+                    method.name(),
+                    "The error type of this method may be the same as the error type of the `{}` \
+                    method, but it is spelled differently",
+                    previous_method;
+                    help = "Spell the error types identically, or use `self::` to refer to a local \
+                        type"
+                );
+            }
+
+            let variant = match variants
+                .iter()
+                .find(|(_, variant_type)| variant_type == &error_type)
+            {
+                Some((variant, _)) => variant.clone(),
+                None => {
+                    let variant = Self::variant_name(&error_type, &variants);
+
+                    variants.push((variant.clone(), error_type));
+                    variant_methods.push(method.name().clone());
+                    variant
+                }
+            };
+
+            method.set_error_variant(parse_quote! { #name::#variant });
+        }
+
+        ErrorEnumData { name, variants }
+    }
+
+    /// Check if two differently spelled types may refer to the same type.
+    ///
+    /// This is the case if the path of one type is a suffix of the path of the other type, like
+    /// `io::Error` and `std::io::Error`.
+    fn may_alias(first: &Type, second: &Type) -> bool {
+        match (first, second) {
+            (
+                Type::Path(TypePath {
+                    qself: None,
+                    path: first,
+                }),
+                Type::Path(TypePath {
+                    qself: None,
+                    path: second,
+                }),
+            ) if first != second => {
+                let (shorter, longer) = if first.segments.len() <= second.segments.len() {
+                    (first, second)
+                } else {
+                    (second, first)
+                };
+
+                shorter.leading_colon.is_none()
+                    && shorter
+                        .segments
+                        .iter()
+                        .rev()
+                        .zip(longer.segments.iter().rev())
+                        .all(|(shorter_segment, longer_segment)| shorter_segment == longer_segment)
+            }
+            _ => false,
+        }
+    }
+
+    /// Choose a variant name for the `error_type` that isn't used by the existing `variants`.
+    fn variant_name(error_type: &Type, variants: &[(Ident, Type)]) -> Ident {
+        let base_name = match error_type {
+            Type::Path(path_type) => path_type
+                .path
+                .segments
+                .last()
+                .map(|segment| segment.ident.to_string().to_camel_case())
+                .unwrap_or_else(|| "Error".to_owned()),
+            _ => "Error".to_owned(),
+        };
+
+        let mut name = base_name.clone();
+        let mut suffix = 1;
+
+        while variants.iter().any(|(variant, _)| variant == &name) {
+            suffix += 1;
+            name = format!("{}{}", base_name, suffix);
+        }
+
+        Ident::new(&name, Span::call_site())
+    }
+
+    /// Return the types used inside the declaration of the `Error` enum.
+    pub fn declared_types(&self) -> impl Iterator<Item = &Type> {
+        self.variants.iter().map(|(_, variant_type)| variant_type)
+    }
+
+    /// Return the `Error` type, including its generic arguments.
+    ///
+    /// The `generics` should be the generic parameters used by the [`Self::declared_types`].
+    pub fn error_type(&self, generics: &Generics) -> Type {
+        let name = &self.name;
+        let (_, type_generics, _) = generics.split_for_impl();
+
+        parse_quote! { #name #type_generics }
+    }
+
+    /// Generate the declaration of the `Error` enum and its trait implementations.
+    ///
+    /// The enum implements [`From`] for each of the wrapped error types. Its [`Debug`],
+    /// [`Display`][std::fmt::Display] and [`Error`][std::error::Error] implementations delegate
+    /// to the wrapped errors, and are only available if all wrapped errors implement the
+    /// respective trait.
+    ///
+    /// The `generics` should be the generic parameters used by the [`Self::declared_types`], and
+    /// the `attributes` are placed on the enum.
+    pub fn declaration(&self, generics: &Generics, attributes: TokenStream) -> TokenStream {
+        let name = &self.name;
+        let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+        let variants = self
+            .variants
+            .iter()
+            .map(|(variant, variant_type)| quote! { #variant(#variant_type) });
+        let from_impls = self.variants.iter().map(|(variant, variant_type)| {
+            quote! {
+                impl #impl_generics From<#variant_type> for #name #type_generics #where_clause {
+                    fn from(error: #variant_type) -> Self {
+                        #name::#variant(error)
+                    }
+                }
+            }
+        });
+
+        let debug_generics = self.generics_with_bound(generics, quote! { std::fmt::Debug });
+        let display_generics = self.generics_with_bound(generics, quote! { std::fmt::Display });
+        let error_generics =
+            self.generics_with_bound(generics, quote! { std::error::Error + 'static });
+        let (debug_impl_generics, _, debug_where_clause) = debug_generics.split_for_impl();
+        let (display_impl_generics, _, display_where_clause) = display_generics.split_for_impl();
+        let (error_impl_generics, _, error_where_clause) = error_generics.split_for_impl();
+
+        // References to empty enums can't be matched exhaustively without dereferencing them.
+        let scrutinee = if self.variants.is_empty() {
+            quote! { *self }
+        } else {
+            quote! { self }
+        };
+        let variant_names = self.variants.iter().map(|(variant, _)| variant);
+        let debug_arms = variant_names.clone().map(|variant| {
+            let variant_string = variant.to_string();
+
+            quote! {
+                #name::#variant(error) => formatter.debug_tuple(#variant_string).field(error).finish()
+            }
+        });
+        let display_arms = variant_names.clone().map(|variant| {
+            quote! { #name::#variant(error) => std::fmt::Display::fmt(error, formatter) }
+        });
+        let source_arms = variant_names.map(|variant| {
+            quote! { #name::#variant(error) => std::error::Error::source(error) }
+        });
+
+        quote! {
+            #attributes
+            pub enum #name #impl_generics #where_clause {
+                #( #variants ),*
+            }
+
+            #( #from_impls )*
+
+            impl #debug_impl_generics std::fmt::Debug for #name #type_generics
+            #debug_where_clause
+            {
+                fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    match #scrutinee {
+                        #( #debug_arms ),*
+                    }
+                }
+            }
+
+            impl #display_impl_generics std::fmt::Display for #name #type_generics
+            #display_where_clause
+            {
+                fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    match #scrutinee {
+                        #( #display_arms ),*
+                    }
+                }
+            }
+
+            impl #error_impl_generics std::error::Error for #name #type_generics
+            #error_where_clause
+            {
+                fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                    match #scrutinee {
+                        #( #source_arms ),*
+                    }
+                }
+            }
+        }
+    }
+
+    /// Add a `bound` for each wrapped error type to the `generics`.
+    ///
+    /// The bounds are [`GenericsData::trivially_bounded`] predicates, so that they aren't rejected
+    /// when a wrapped error type is a concrete type that doesn't implement the trait.
+    fn generics_with_bound(&self, generics: &Generics, bound: TokenStream) -> Generics {
+        let mut generics = generics.clone();
+        let predicates = self
+            .variants
+            .iter()
+            .map(|(_, variant_type)| GenericsData::trivially_bounded(variant_type, bound.clone()));
+
+        generics.make_where_clause().predicates.extend(predicates);
+        generics
+    }
+}
@@ -1,16 +1,52 @@
 use {
-    super::{method_data::MethodData, receiver_type::ReceiverType, response_data::ResponseData},
+    super::{
+        arguments::{Concurrency, MacroArguments, Tagging},
+        error_enum_data::ErrorEnumData,
+        generics_data::GenericsData,
+        method_attributes::MethodAttributes,
+        method_data::MethodData,
+        receiver_type::ReceiverType,
+        response_data::ResponseData,
+        trait_data::TraitData,
+    },
     proc_macro2::TokenStream,
-    proc_macro_error::abort,
+    proc_macro_error::{abort, emit_error},
     quote::quote,
-    syn::{ImplItem, ItemImpl, Type},
+    syn::{
+        parse_quote, Generics, ImplItem, ItemImpl, ItemTrait, TraitItem, Type, Visibility,
+        WherePredicate,
+    },
 };
 
 /// Code generator for a [`tower::Service`] RPC.
 pub struct Generator {
+    /// The arguments passed to the macro attribute.
+    arguments: MacroArguments,
+
     /// The type the `impl` block is for.
+    ///
+    /// If the macro was applied to a trait, this is the type parameter that represents the trait
+    /// implementation.
     self_type: Type,
 
+    /// The trait that defines the RPC interface, if the macro was applied to a trait.
+    trait_data: Option<TraitData>,
+
+    /// The generic parameters of the `impl` block.
+    generics: GenericsData,
+
+    /// The generic parameters used by the `Request` type.
+    request_generics: Generics,
+
+    /// The generic parameters used by the `Response` type.
+    response_generics: Generics,
+
+    /// The generated `Error` enum, if one was requested.
+    error_enum: Option<ErrorEnumData>,
+
+    /// The generic parameters used by the `Error` enum.
+    error_generics: Generics,
+
     /// The meta-data for each method in the `impl` block.
     methods: Vec<MethodData>,
 
@@ -23,23 +59,155 @@ pub struct Generator {
 
 impl Generator {
     /// Create a [`Generator`] after extracting the necessary meta-data from an [`ItemImpl`].
-    pub fn new(item: &ItemImpl) -> Self {
+    pub fn new(item: &ItemImpl, arguments: MacroArguments) -> Self {
         let self_type = item.self_ty.as_ref().clone();
+        let generics = GenericsData::new(&item.generics);
+        let item_error = MethodAttributes::item_error(&item.attrs);
 
         let methods: Vec<_> = item
             .items
             .iter()
             .filter_map(|item| match item {
-                ImplItem::Method(method) => Some(MethodData::new(method)),
+                ImplItem::Method(method) => Some(method),
                 _ => None,
             })
+            .filter(|method| {
+                !arguments.skip_private() || matches!(method.vis, Visibility::Public(_))
+            })
+            .filter_map(|method| {
+                let attributes = MethodAttributes::new(&method.attrs);
+
+                (!attributes.skip())
+                    .then(|| MethodData::new(&method.sig, &attributes, item_error.as_ref()))
+            })
             .collect();
 
         if methods.is_empty() {
             abort!(item, "`impl` item has no methods");
         }
 
-        let response = ResponseData::new(&methods);
+        Self::with_methods(arguments, self_type, None, generics, methods)
+    }
+
+    /// Create a [`Generator`] after extracting the necessary meta-data from an [`ItemTrait`].
+    ///
+    /// The generated `Service` is generic over the type that implements the trait, and a `Client`
+    /// type that implements the trait by sending `Request`s to a [`tower::Service`] is also
+    /// generated.
+    pub fn from_trait(item: &ItemTrait, arguments: MacroArguments) -> Self {
+        let trait_data = TraitData::new(item);
+        let self_type = trait_data.implementation_type();
+        let item_error = MethodAttributes::item_error(&item.attrs);
+
+        let methods: Vec<_> = item
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                TraitItem::Method(method) => Some(method),
+                _ => None,
+            })
+            .filter_map(|method| {
+                let attributes = MethodAttributes::new(&method.attrs);
+
+                if !attributes.skip() {
+                    Some(MethodData::new(
+                        &method.sig,
+                        &attributes,
+                        item_error.as_ref(),
+                    ))
+                } else if method.default.is_none() {
+                    abort!(
+                        method.sig.ident,
+                        "Skipped trait methods must have a default implementation"
+                    );
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        if methods.is_empty() {
+            abort!(item, "trait has no methods");
+        }
+
+        for method in &methods {
+            if method.receiver_type() == ReceiverType::NoReceiver {
+                abort!(
+                    method.name(),
+                    "Trait methods without a `self` receiver can't be called through the client"
+                );
+            }
+
+            if !method.is_asynchronous() {
+                abort!(
+                    method.name(),
+                    "Trait methods must be `async` to be called through the client"
+                );
+            }
+
+            if method.result().err_type().is_none() {
+                abort!(
+                    method.name(),
+                    "Trait methods must return a `Result`, so that the client can report failures \
+                    to call the service";
+                    help = "Return a `Result<T, E>` where `E` implements \
+                        `From<ezrpc::CallError<E>>`"
+                );
+            }
+
+            if method.result().is_borrowed() {
+                abort!(
+                    method.name(),
+                    "Trait methods that return references can't be called through the client"
+                );
+            }
+        }
+
+        let mut service_generics = trait_data.service_generics(arguments.is_local());
+
+        if methods
+            .iter()
+            .any(|method| method.receiver_type() == ReceiverType::Owned)
+        {
+            let clone_bound: WherePredicate = parse_quote! { #self_type: Clone };
+
+            service_generics
+                .make_where_clause()
+                .predicates
+                .push(clone_bound);
+        }
+
+        let generics = GenericsData::new(&service_generics);
+
+        Self::with_methods(arguments, self_type, Some(trait_data), generics, methods)
+    }
+
+    /// Create a [`Generator`] from the extracted meta-data.
+    fn with_methods(
+        arguments: MacroArguments,
+        self_type: Type,
+        trait_data: Option<TraitData>,
+        generics: GenericsData,
+        mut methods: Vec<MethodData>,
+    ) -> Self {
+        Self::check_name_collisions(&methods, arguments.tagging());
+
+        let error_enum = arguments
+            .error_enum()
+            .map(|name| ErrorEnumData::new(name.clone(), &mut methods));
+        let error_generics = match &error_enum {
+            Some(error_enum) => generics.subset_for(error_enum.declared_types()),
+            None => Generics::default(),
+        };
+        let error_type = error_enum
+            .as_ref()
+            .map(|error_enum| error_enum.error_type(&error_generics));
+
+        let response = ResponseData::new(&methods, error_type);
+
+        let request_generics =
+            generics.subset_for(methods.iter().flat_map(MethodData::field_types));
+        let response_generics = generics.subset_for(response.declared_types());
 
         let receiver_type = methods
             .iter()
@@ -47,22 +215,182 @@ impl Generator {
             .max()
             .expect("There is at least one method");
 
+        if receiver_type == ReceiverType::MutableReference {
+            if let Some(method) = methods
+                .iter()
+                .find(|method| method.receiver_type() == ReceiverType::Arc)
+            {
+                abort!(
+                    method.name(),
+                    "Methods with a `self: Arc<Self>` receiver can't be mixed with methods with a \
+                    `&mut self` receiver"
+                );
+            }
+        }
+
+        if arguments.concurrency() == Concurrency::Actor {
+            for method in &methods {
+                if method.receiver_type() == ReceiverType::Arc {
+                    abort!(
+                        method.name(),
+                        "Methods with a `self: Arc<Self>` receiver can't be used with \
+                        `concurrency = actor`"
+                    );
+                }
+            }
+        }
+
+        let constructor_names: &[&str] = match (arguments.concurrency(), receiver_type) {
+            (Concurrency::Actor, _) => &["actor"],
+            (Concurrency::Lock, ReceiverType::NoReceiver) => &["new"],
+            (Concurrency::Lock, _) => &["new", "from_shared", "inner"],
+        };
+
+        if let Some(method) = methods.iter().find(|method| {
+            constructor_names
+                .iter()
+                .any(|constructor_name| method.name() == constructor_name)
+        }) {
+            abort!(
+                method.name(),
+                "The `{}` method clashes with the generated `{}::{}` method",
+                method.name(),
+                arguments.service(),
+                method.name();
+                help = "Rename the method or skip it with `#[ezrpc(skip)]`"
+            );
+        }
+
+        if let Some(remote_client) = arguments.remote_client() {
+            if let Some(method) = methods.iter().find(|method| method.name() == "new") {
+                abort!(
+                    method.name(),
+                    "The `new` method clashes with the generated `{}::new` method",
+                    remote_client;
+                    help = "Rename the method or skip it with `#[ezrpc(skip)]`"
+                );
+            }
+        }
+
         Generator {
+            arguments,
             self_type,
+            trait_data,
+            generics,
+            request_generics,
+            response_generics,
+            error_enum,
+            error_generics,
             methods,
             response,
             receiver_type,
         }
     }
 
+    /// Check that the generated names don't collide with each other.
+    ///
+    /// Aborts if two methods would generate the same `Request` variant, if a parameter name
+    /// would shadow a name used by the generated code, or if a parameter name would clash with a
+    /// field added by the `tagging`.
+    fn check_name_collisions(methods: &[MethodData], tagging: Tagging) {
+        for (index, method) in methods.iter().enumerate() {
+            let request_name = method.request_name();
+
+            if let Some(previous_method) = methods[..index]
+                .iter()
+                .find(|previous_method| previous_method.request_name() == request_name)
+            {
+                emit_error!(
+                    previous_method.name(),
+                    "The `{}` request variant for this method is also used by the `{}` method",
+                    request_name,
+                    method.name()
+                );
+                abort!(
+                    method.name(),
+                    "The `{}` request variant for this method is already used by the `{}` method",
+                    request_name,
+                    previous_method.name();
+                    help = "Use `#[ezrpc(rename = \"...\")]` to choose a different variant name"
+                );
+            }
+
+            for parameter_name in method.parameter_names() {
+                if MethodData::RESERVED_NAMES
+                    .iter()
+                    .any(|reserved_name| parameter_name == reserved_name)
+                {
+                    abort!(
+                        parameter_name,
+                        "The `{}` parameter name clashes with a name used in the generated code",
+                        parameter_name;
+                        help = "Rename the parameter"
+                    );
+                }
+
+                if tagging
+                    .reserved_field_names()
+                    .iter()
+                    .any(|reserved_name| parameter_name == reserved_name)
+                {
+                    abort!(
+                        parameter_name,
+                        "The `{}` parameter name clashes with the `{}` tag field of the serialized \
+                        `Request`",
+                        parameter_name,
+                        parameter_name;
+                        help = "Rename the parameter, or use `tagging = adjacent`"
+                    );
+                }
+            }
+        }
+    }
+
+    /// Generate all the items for the RPC interface.
+    ///
+    /// If a module name was specified in the macro arguments, the items are wrapped inside a module
+    /// with that name.
+    pub fn generated_items(&self) -> TokenStream {
+        let request = self.request();
+        let response = self.response();
+        let error_enum = self.error_enum();
+        let service = self.service();
+        let client = self.client();
+        let remote_client = self.remote_client();
+
+        let items = quote! {
+            #request
+            #response
+            #error_enum
+            #service
+            #client
+            #remote_client
+        };
+
+        match self.arguments.module() {
+            Some(module) => quote! {
+                pub mod #module {
+                    use super::*;
+
+                    #items
+                }
+            },
+            None => items,
+        }
+    }
+
     /// Generate the `Request` enum type for sending to the generated [`tower::Service`].
     ///
     /// Contains one variant for each method, in order to determine which method to call.
-    pub fn request(&self) -> TokenStream {
+    fn request(&self) -> TokenStream {
+        let request = self.arguments.request();
+        let attributes = self.arguments.request_attributes();
+        let (generics, _, where_clause) = self.request_generics.split_for_impl();
         let variants = self.methods.iter().map(MethodData::request_enum_variant);
 
         quote! {
-            pub enum Request {
+            #attributes
+            pub enum #request #generics #where_clause {
                 #( #variants ),*
             }
         }
@@ -72,26 +400,59 @@ impl Generator {
     ///
     /// Can either be a shared type for all methods, or an enumeration with one variant for each
     /// method, representing which method was called by the service.
-    pub fn response(&self) -> TokenStream {
-        self.response.response_type_declaration()
+    fn response(&self) -> TokenStream {
+        self.response.response_type_declaration(
+            self.arguments.response(),
+            &self.response_generics,
+            &self.arguments.response_attributes(),
+        )
+    }
+
+    /// Generate the `Error` enum used as the error type of the generated [`tower::Service`], if
+    /// one was requested.
+    fn error_enum(&self) -> TokenStream {
+        match &self.error_enum {
+            Some(error_enum) => {
+                error_enum.declaration(&self.error_generics, self.arguments.error_enum_attributes())
+            }
+            None => quote! {},
+        }
     }
 
     /// Generate the `Service` type and its [`tower::Service`] implementation.
     ///
     /// The `Service` type receives `Request`s and dispatches them to the method implementations in
     /// the input `impl` block.
-    pub fn service(&self) -> TokenStream {
+    fn service(&self) -> TokenStream {
+        let service = self.arguments.service();
+        let declaration_generics = self.generics.declaration();
+        let declaration_where_clause = &declaration_generics.where_clause;
+        let implementation_generics = self.generics.implementation();
+        let (impl_generics, type_generics, where_clause) = implementation_generics.split_for_impl();
         let service_data = self.service_data();
         let service_impl = self.service_impl();
-        let service_methods = self.methods.iter().map(MethodData::service_method);
+        let constructors = match self.arguments.concurrency() {
+            Concurrency::Lock => self.lock_constructors(),
+            Concurrency::Actor => self.actor_constructor(),
+        };
+        let clone_impl = self.clone_impl();
+        let default_impl = self.default_impl();
+        let service_methods = self
+            .methods
+            .iter()
+            .map(|method| method.service_method(&self.response, &self.arguments));
 
         quote! {
-            pub struct Service #service_data;
+            pub struct #service #declaration_generics #service_data #declaration_where_clause;
 
-            impl Service {
+            impl #impl_generics #service #type_generics #where_clause {
+                #constructors
                 #( #service_methods )*
             }
 
+            #clone_impl
+            #default_impl
+
             #service_impl
         }
     }
@@ -99,70 +460,602 @@ impl Generator {
     /// Generate the inner field inside the `Service` type.
     ///
     /// This contains a shared reference to the instance that implements the method behaviour. It
-    /// is used for methods that require a `&self` or a `&mut self` receiver. The instance is
-    /// wrapped in an `Arc`, because the `Service` type may live less than the response
-    /// [`Future`][std::future::Future] it returns. If there's at least one method that uses a
-    /// `&mut self` receiver, then the instance is also wrapped inside a
-    /// [`RwLock`][tokio::sync::RwLock], to avoid concurrent access to it.
+    /// is used for methods that require a `self`, `&self`, `self: Arc<Self>` or a `&mut self`
+    /// receiver. The instance is wrapped in an `Arc`, because the `Service` type may live less
+    /// than the response [`Future`][std::future::Future] it returns. If there's at least one
+    /// method that uses a `&mut self` receiver, then the instance is also wrapped inside a
+    /// `RwLock` (from the lock backend selected in `ezrpc`), to avoid concurrent access to it.
+    ///
+    /// With `concurrency = actor`, the field is instead the sender side of the actor's mailbox.
+    ///
+    /// If the `impl` block or trait is generic, the fields are followed by a
+    /// [`PhantomData`][std::marker::PhantomData] field that uses the type parameters, because they
+    /// aren't necessarily used by the instance's type.
     fn service_data(&self) -> TokenStream {
+        let private = self.arguments.private_module();
+        let field = if self.arguments.concurrency() == Concurrency::Actor {
+            let message = self.mailbox_message();
+
+            Some(quote! { #private::futures::channel::mpsc::Sender<#message> })
+        } else {
+            self.shared_type()
+        };
+        let fields: Vec<_> = field
+            .into_iter()
+            .chain(self.generics.phantom_data())
+            .collect();
+
+        if fields.is_empty() {
+            quote! {}
+        } else {
+            quote! { ( #( #fields ),* ) }
+        }
+    }
+
+    /// Generate the type used to share the instance between the calls, if the methods require an
+    /// instance.
+    ///
+    /// Only used if the `Service` doesn't use an actor.
+    fn shared_type(&self) -> Option<TokenStream> {
         let self_type = &self.self_type;
+        let private = self.arguments.private_module();
 
         match self.receiver_type {
-            ReceiverType::NoReceiver => quote! {},
-            ReceiverType::Reference => quote! { (std::sync::Arc<#self_type>) },
+            ReceiverType::NoReceiver => None,
+            ReceiverType::Owned | ReceiverType::Reference | ReceiverType::Arc => {
+                Some(quote! { std::sync::Arc<#self_type> })
+            }
             ReceiverType::MutableReference => {
-                quote! { (std::sync::Arc<tokio::sync::RwLock<#self_type>>) }
+                Some(quote! { std::sync::Arc<#private::RwLock<#self_type>> })
+            }
+        }
+    }
+
+    /// Generate the expression that creates the `Service` from the expression for its `field`.
+    ///
+    /// The `field` is either the shared instance, the actor's mailbox or nothing, if the methods
+    /// don't require an instance. A [`PhantomData`][std::marker::PhantomData] is added if the
+    /// `Service` is generic.
+    fn service_construction(&self, field: Option<TokenStream>) -> TokenStream {
+        let service = self.arguments.service();
+        let phantom_data = self
+            .generics
+            .phantom_data()
+            .map(|_| quote! { std::marker::PhantomData });
+        let fields: Vec<_> = field.into_iter().chain(phantom_data).collect();
+
+        if fields.is_empty() {
+            quote! { #service }
+        } else {
+            quote! { #service( #( #fields ),* ) }
+        }
+    }
+
+    /// Generate the type of the messages sent to the actor's mailbox.
+    ///
+    /// Each message contains the `Request` and the sender to use for the response.
+    fn mailbox_message(&self) -> TokenStream {
+        let private = self.arguments.private_module();
+        let request = self.arguments.request();
+        let (_, request_type_generics, _) = self.request_generics.split_for_impl();
+        let response = self.response_type();
+        let response = self.response.ok_type(&response);
+        let error = self.response.err_type();
+
+        quote! {
+            (
+                #request #request_type_generics,
+                #private::futures::channel::oneshot::Sender<Result<#response, #error>>,
+            )
+        }
+    }
+
+    /// Generate the constructor for a `Service` that uses an actor.
+    ///
+    /// The constructor moves the instance into the actor's [`Future`][std::future::Future], which
+    /// is returned together with the `Service` so that it can be spawned on any runtime. The actor
+    /// executes the received `Request`s serially, and stops once all `Service` handles are
+    /// dropped.
+    fn actor_constructor(&self) -> TokenStream {
+        let self_type = &self.self_type;
+        let private = self.arguments.private_module();
+        let message = self.mailbox_message();
+        let send_assertion = self.send_assertion();
+        let request_match_arms = self.request_match_arms();
+        let service = self.service_construction(Some(quote! { sender }));
+        let mutability = if self.receiver_type == ReceiverType::MutableReference {
+            quote! { mut }
+        } else {
+            quote! {}
+        };
+
+        quote! {
+            /// Create a `Service` that sends its requests to an actor that owns the `instance`.
+            ///
+            /// Returns the `Service` together with the actor's future, which must be spawned for
+            /// the requests to be handled. The actor's mailbox buffers up to `mailbox_capacity`
+            /// requests, and the actor stops once all `Service` handles are dropped.
+            pub fn actor(
+                instance: #self_type,
+                mailbox_capacity: usize,
+            ) -> (Self, impl std::future::Future<Output = ()>) {
+                use #private::futures::StreamExt as _;
+
+                #send_assertion
+
+                let (sender, mut mailbox) =
+                    #private::futures::channel::mpsc::channel::<#message>(mailbox_capacity);
+
+                let actor = async move {
+                    let #mutability inner = instance;
+
+                    while let Some((request, responder)) = mailbox.next().await {
+                        let response = match request {
+                            #( #request_match_arms ),*
+                        };
+
+                        let _ = responder.send(response);
+                    }
+                };
+
+                (#service, actor)
             }
         }
     }
 
+    /// Generate the constructors and accessor for a `Service` that doesn't use an actor.
+    ///
+    /// If the methods require an instance, the `Service` can be created from the instance or from
+    /// an already shared instance, and the shared instance can be accessed afterwards. Otherwise,
+    /// the `Service` is created without any arguments.
+    fn lock_constructors(&self) -> TokenStream {
+        let self_type = &self.self_type;
+        let private = self.arguments.private_module();
+
+        let shared_type = match self.shared_type() {
+            Some(shared_type) => shared_type,
+            None => {
+                let service = self.service_construction(None);
+
+                return quote! {
+                    /// Create a new `Service`.
+                    pub fn new() -> Self {
+                        #service
+                    }
+                };
+            }
+        };
+
+        let shared_instance = if self.receiver_type == ReceiverType::MutableReference {
+            quote! { std::sync::Arc::new(#private::RwLock::new(instance)) }
+        } else {
+            quote! { std::sync::Arc::new(instance) }
+        };
+        let service = self.service_construction(Some(quote! { shared }));
+
+        quote! {
+            /// Create a new `Service` that dispatches requests to the `instance`.
+            pub fn new(instance: #self_type) -> Self {
+                Self::from_shared(#shared_instance)
+            }
+
+            /// Create a new `Service` that dispatches requests to an already `shared` instance.
+            pub fn from_shared(shared: #shared_type) -> Self {
+                #service
+            }
+
+            /// Retrieve the shared instance that requests are dispatched to.
+            pub fn inner(&self) -> &#shared_type {
+                &self.0
+            }
+        }
+    }
+
+    /// Generate the [`Clone`] implementation for the `Service`.
+    ///
+    /// Cloning the `Service` only clones the handle to the shared instance or the actor's mailbox.
+    fn clone_impl(&self) -> TokenStream {
+        let implementation_generics = self.generics.implementation();
+        let (impl_generics, type_generics, where_clause) = implementation_generics.split_for_impl();
+        let service_type = self.arguments.service();
+        let has_field = self.arguments.concurrency() == Concurrency::Actor
+            || self.receiver_type != ReceiverType::NoReceiver;
+        let service = self.service_construction(has_field.then(|| quote! { self.0.clone() }));
+
+        quote! {
+            impl #impl_generics Clone for #service_type #type_generics #where_clause {
+                fn clone(&self) -> Self {
+                    #service
+                }
+            }
+        }
+    }
+
+    /// Generate the [`Default`] implementation for a `Service` that doesn't use an actor.
+    ///
+    /// If the methods require an instance, the implementation requires the instance type to
+    /// implement [`Default`], using a [`GenericsData::trivially_bounded`] predicate so that the
+    /// implementation is only rejected where it's used.
+    fn default_impl(&self) -> TokenStream {
+        if self.arguments.concurrency() == Concurrency::Actor {
+            return quote! {};
+        }
+
+        let self_type = &self.self_type;
+        let service = self.arguments.service();
+        let mut implementation_generics = self.generics.implementation();
+        let (_, type_generics, _) = self.generics.declaration().split_for_impl();
+
+        let construction = if self.receiver_type == ReceiverType::NoReceiver {
+            quote! { Self::new() }
+        } else {
+            implementation_generics.make_where_clause().predicates.push(
+                GenericsData::trivially_bounded(self_type, quote! { Default }),
+            );
+
+            quote! { Self::new(Default::default()) }
+        };
+
+        let (impl_generics, _, where_clause) = implementation_generics.split_for_impl();
+
+        quote! {
+            impl #impl_generics Default for #service #type_generics #where_clause {
+                fn default() -> Self {
+                    #construction
+                }
+            }
+        }
+    }
+
+    /// Generate the function used to check that the futures returned by the methods are [`Send`].
+    ///
+    /// The function name is prefixed so that it isn't shadowed by a method parameter. Nothing is
+    /// generated if the service is `local`.
+    fn send_assertion(&self) -> TokenStream {
+        if self.arguments.is_local() {
+            quote! {}
+        } else {
+            quote! {
+                fn __ezrpc_assert_send<F: std::future::Future + Send>(future: F) -> F {
+                    future
+                }
+            }
+        }
+    }
+
+    /// Generate the match arms that dispatch each `Request` variant to its method.
+    fn request_match_arms(&self) -> Vec<TokenStream> {
+        self.methods
+            .iter()
+            .map(|method| {
+                method.request_match_arm(
+                    self.receiver_type,
+                    &self.self_type,
+                    &self.response,
+                    &self.arguments,
+                )
+            })
+            .collect()
+    }
+
     /// Generate the implementation of the [`tower::Service`] trait for the generated `Service`
     /// type.
     ///
     /// The implementation is a large dispatcher, that calls the methods in the input `impl` block.
+    /// With `concurrency = actor`, the requests are sent to the actor instead, and the `Service`
+    /// is only ready when there is room in the actor's mailbox. Since the actor may stop, errors
+    /// are reported as a `CallError`.
     fn service_impl(&self) -> TokenStream {
-        let service_data_binding = self.service_data_binding();
-        let request_match_arms = self.methods.iter().map(|method| {
-            method.request_match_arm(self.receiver_type, &self.self_type, &self.response)
-        });
-        let response = self.response.ok_type();
+        let request = self.arguments.request();
+        let (_, request_type_generics, _) = self.request_generics.split_for_impl();
+        let service = self.arguments.service();
+        let implementation_generics = self.generics.implementation();
+        let (impl_generics, type_generics, where_clause) = implementation_generics.split_for_impl();
+        let response = self.response_type();
+        let response = self.response.ok_type(&response);
         let error = self.response.err_type();
+        let crate_path = self.arguments.crate_path();
+        let private = self.arguments.private_module();
+        let error = match self.arguments.concurrency() {
+            Concurrency::Lock => error,
+            Concurrency::Actor => quote! { #crate_path::CallError<#error> },
+        };
+        let (send_bound, boxed) = if self.arguments.is_local() {
+            (quote! {}, quote! { boxed_local })
+        } else {
+            (quote! { + Send }, quote! { boxed })
+        };
+        let (poll_ready, call) = match self.arguments.concurrency() {
+            Concurrency::Lock => (self.lock_poll_ready(), self.lock_call()),
+            Concurrency::Actor => (self.actor_poll_ready(), self.actor_call()),
+        };
 
         quote! {
-            impl tower::Service<Request> for Service {
+            impl #impl_generics #private::tower::Service<#request #request_type_generics>
+                for #service #type_generics #where_clause
+            {
                 type Response = #response;
                 type Error = #error;
                 type Future = std::pin::Pin<Box<
                     dyn std::future::Future<Output = Result<Self::Response, Self::Error>>
+                        #send_bound
                 >>;
 
                 fn poll_ready(
                     &mut self,
                     context: &mut std::task::Context<'_>,
                 ) -> std::task::Poll<Result<(), Self::Error>> {
-                    std::task::Poll::Ready(Ok(()))
+                    #poll_ready
                 }
 
-                fn call(&mut self, request: Request) -> Self::Future {
-                    use futures::FutureExt as _;
+                fn call(&mut self, request: #request #request_type_generics) -> Self::Future {
+                    use #private::futures::FutureExt as _;
+
+                    #call.#boxed()
+                }
+            }
+        }
+    }
+
+    /// Generate the body of [`tower::Service::poll_ready`] for a `Service` that shares the
+    /// instance.
+    ///
+    /// The `Service` is always ready.
+    fn lock_poll_ready(&self) -> TokenStream {
+        quote! {
+            std::task::Poll::Ready(Ok(()))
+        }
+    }
+
+    /// Generate the body of [`tower::Service::call`] for a `Service` that shares the instance.
+    ///
+    /// The generated code results in a [`Future`][std::future::Future] that calls the method
+    /// requested.
+    fn lock_call(&self) -> TokenStream {
+        let service_data_binding = self.service_data_binding();
+        let send_assertion = self.send_assertion();
+        let request_match_arms = self.request_match_arms();
 
-                    #service_data_binding
+        quote! {
+            #send_assertion
+            #service_data_binding
 
-                    async move {
-                        match request {
-                            #( #request_match_arms ),*
-                        }
-                    }.boxed()
+            async move {
+                match request {
+                    #( #request_match_arms ),*
                 }
             }
         }
     }
 
+    /// Generate the body of [`tower::Service::poll_ready`] for a `Service` that uses an actor.
+    ///
+    /// The `Service` is ready when there's room in the actor's mailbox, and fails with
+    /// `CallError::Unavailable` if the actor has stopped.
+    fn actor_poll_ready(&self) -> TokenStream {
+        let crate_path = self.arguments.crate_path();
+
+        quote! {
+            self.0
+                .poll_ready(context)
+                .map_err(|_| #crate_path::CallError::Unavailable)
+        }
+    }
+
+    /// Generate the body of [`tower::Service::call`] for a `Service` that uses an actor.
+    ///
+    /// The generated code sends the request to the actor's mailbox, and results in a
+    /// [`Future`][std::future::Future] that waits for the actor's response. If the actor has
+    /// stopped, the [`Future`][std::future::Future] fails with `CallError::Unavailable`.
+    fn actor_call(&self) -> TokenStream {
+        let crate_path = self.arguments.crate_path();
+        let private = self.arguments.private_module();
+
+        quote! {
+            let (responder, response) = #private::futures::channel::oneshot::channel();
+            let sent = self.0.start_send((request, responder)).is_ok();
+
+            async move {
+                if !sent {
+                    return Err(#crate_path::CallError::Unavailable);
+                }
+
+                match response.await {
+                    Ok(result) => result.map_err(#crate_path::CallError::Failed),
+                    Err(_) => Err(#crate_path::CallError::Unavailable),
+                }
+            }
+        }
+    }
+
+    /// Generate the `Client` type and its implementation of the RPC interface trait.
+    ///
+    /// The `Client` wraps a [`tower::Service`] that handles the generated `Request` type, and
+    /// implements the trait by sending a `Request` for each method call. The wrapped service's
+    /// error only has to be convertible into a `CallError`, so that the `Client` can wrap the
+    /// generated `Service` (with any concurrency) as well as a `RemoteClient`. Failures of the
+    /// wrapped service are reported through the trait methods' error types. This is only generated
+    /// if the macro was applied to a trait.
+    fn client(&self) -> TokenStream {
+        let trait_data = match &self.trait_data {
+            Some(trait_data) => trait_data,
+            None => return quote! {},
+        };
+
+        let client = self.arguments.client();
+        let request = self.arguments.request();
+        let (_, request_type_generics, _) = self.request_generics.split_for_impl();
+        let response = self.response_type();
+        let response = self.response.ok_type(&response);
+        let error = self.response.err_type();
+        let trait_path = trait_data.path();
+        let service_parameter = trait_data.unique_parameter("S");
+        let private = self.arguments.private_module();
+        let mut generics = trait_data.generics().clone();
+
+        generics
+            .params
+            .insert(0, parse_quote! { #service_parameter });
+        let crate_path = self.arguments.crate_path();
+        let service_bounds: [WherePredicate; 2] = [
+            parse_quote! {
+                #service_parameter: #private::tower::Service<
+                    #request #request_type_generics,
+                    Response = #response,
+                > + Clone
+            },
+            parse_quote! {
+                #service_parameter::Error: Into<#crate_path::CallError<#error>>
+            },
+        ];
+
+        generics
+            .make_where_clause()
+            .predicates
+            .extend(service_bounds);
+
+        if !self.arguments.is_local() {
+            generics
+                .make_where_clause()
+                .predicates
+                .extend::<[WherePredicate; 3]>([
+                    parse_quote! { #service_parameter: Send + Sync },
+                    parse_quote! { #service_parameter::Future: Send },
+                    parse_quote! { #service_parameter::Error: Send },
+                ]);
+        }
+
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let client_methods = self
+            .methods
+            .iter()
+            .map(|method| method.client_method(&self.response, &self.arguments));
+
+        quote! {
+            pub struct #client<#service_parameter>(#service_parameter);
+
+            impl<#service_parameter> #client<#service_parameter> {
+                /// Create a client that sends its requests to the `service`.
+                pub fn new(service: #service_parameter) -> Self {
+                    #client(service)
+                }
+            }
+
+            impl #impl_generics #trait_path for #client<#service_parameter> #where_clause {
+                #( #client_methods )*
+            }
+        }
+    }
+
+    /// Generate the `RemoteClient` type, if one was requested.
+    ///
+    /// The `RemoteClient` sends `Request`s to a remote `Service` through a `Connection`, and
+    /// implements [`tower::Service`] and the same helper methods as the generated `Service`.
+    /// Failures of the connection are reported as a `CallError`.
+    fn remote_client(&self) -> TokenStream {
+        let remote_client = match self.arguments.remote_client() {
+            Some(remote_client) => remote_client,
+            None => return quote! {},
+        };
+
+        let request = self.arguments.request();
+        let (_, request_type_generics, _) = self.request_generics.split_for_impl();
+        let response = self.response_type();
+        let response = self.response.ok_type(&response);
+        let error = self.response.err_type();
+        let crate_path = self.arguments.crate_path();
+        let private = self.arguments.private_module();
+        let generics = self.generics.subset_for(
+            self.methods
+                .iter()
+                .flat_map(MethodData::field_types)
+                .chain(self.response.result_types()),
+        );
+        let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+        let request_type = quote! { #request #request_type_generics };
+        let remote_client_methods = self
+            .methods
+            .iter()
+            .map(|method| method.remote_client_method(&self.response, &self.arguments));
+
+        quote! {
+            pub struct #remote_client #generics (
+                #crate_path::client::Connection<#request_type, #response, #error>,
+            )
+            #where_clause;
+
+            impl #impl_generics #remote_client #type_generics #where_clause {
+                /// Create a client that sends its requests through the `requests` sink and
+                /// receives the responses from the `responses` stream.
+                ///
+                /// Returns the client and the background task that must be spawned to drive the
+                /// connection.
+                pub fn new<Requests, Responses, TransportError>(
+                    requests: Requests,
+                    responses: Responses,
+                ) -> (Self, impl std::future::Future<Output = ()>)
+                where
+                    Requests: #private::futures::Sink<(#crate_path::RequestId, #request_type)>,
+                    Responses: #private::futures::Stream<
+                        Item = Result<
+                            (#crate_path::RequestId, Result<#response, #error>),
+                            TransportError,
+                        >,
+                    >,
+                {
+                    let (connection, task) =
+                        #crate_path::client::Connection::new(requests, responses);
+
+                    (#remote_client(connection), task)
+                }
+
+                #( #remote_client_methods )*
+            }
+
+            impl #impl_generics Clone for #remote_client #type_generics #where_clause {
+                fn clone(&self) -> Self {
+                    #remote_client(self.0.clone())
+                }
+            }
+
+            impl #impl_generics #private::tower::Service<#request_type>
+                for #remote_client #type_generics
+            #where_clause
+            {
+                type Response = #response;
+                type Error = #crate_path::CallError<#error>;
+                type Future = #crate_path::client::ResponseFuture<#response, #error>;
+
+                fn poll_ready(
+                    &mut self,
+                    context: &mut std::task::Context<'_>,
+                ) -> std::task::Poll<Result<(), Self::Error>> {
+                    #private::tower::Service::poll_ready(&mut self.0, context)
+                }
+
+                fn call(&mut self, request: #request_type) -> Self::Future {
+                    #private::tower::Service::call(&mut self.0, request)
+                }
+            }
+        }
+    }
+
+    /// Generate the generated `Response` type, including its generic parameters.
+    fn response_type(&self) -> TokenStream {
+        let response = self.arguments.response();
+        let (_, type_generics, _) = self.response_generics.split_for_impl();
+
+        quote! { #response #type_generics }
+    }
+
     /// Generate the binding to the inner field inside the `Service` type.
     fn service_data_binding(&self) -> TokenStream {
         match self.receiver_type {
             ReceiverType::NoReceiver => quote! {},
-            ReceiverType::Reference | ReceiverType::MutableReference => quote! {
+            ReceiverType::Owned
+            | ReceiverType::Reference
+            | ReceiverType::Arc
+            | ReceiverType::MutableReference => quote! {
                 let inner = self.0.clone();
             },
         }
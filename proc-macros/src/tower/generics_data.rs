@@ -0,0 +1,187 @@
+use {
+    proc_macro2::TokenStream,
+    proc_macro_error::abort,
+    quote::quote,
+    std::collections::HashSet,
+    syn::{
+        parse_quote,
+        visit::{self, Visit},
+        GenericParam, Generics, Ident, Path, Type, WherePredicate,
+    },
+};
+
+/// Representation of the generic parameters of the `impl` block.
+pub struct GenericsData {
+    /// The generic parameters and where clause of the `impl` block.
+    generics: Generics,
+}
+
+impl GenericsData {
+    /// Create a new [`GenericsData`] from the [`Generics`] of the `impl` block.
+    ///
+    /// Lifetime parameters aren't supported, because the generated
+    /// [`Future`][std::future::Future]s must be `'static`.
+    pub fn new(generics: &Generics) -> Self {
+        if let Some(lifetime) = generics.lifetimes().next() {
+            abort!(lifetime, "Lifetime parameters aren't supported");
+        }
+
+        GenericsData {
+            generics: generics.clone(),
+        }
+    }
+
+    /// Retrieve the generic parameters to use when declaring the `Service` type.
+    pub fn declaration(&self) -> &Generics {
+        &self.generics
+    }
+
+    /// Retrieve the generic parameters to use when implementing traits and methods for the
+    /// `Service` type.
+    ///
+    /// All type parameters are required to be `'static`, so that the returned
+    /// [`Future`][std::future::Future]s can be boxed.
+    pub fn implementation(&self) -> Generics {
+        let mut generics = self.generics.clone();
+        let static_bounds: Vec<WherePredicate> = generics
+            .type_params()
+            .map(|parameter| {
+                let name = &parameter.ident;
+
+                parse_quote! { #name: 'static }
+            })
+            .collect();
+
+        generics
+            .make_where_clause()
+            .predicates
+            .extend(static_bounds);
+
+        generics
+    }
+
+    /// Generate a [`PhantomData`][std::marker::PhantomData] field type that uses all type
+    /// parameters, if there are any.
+    pub fn phantom_data(&self) -> Option<TokenStream> {
+        let type_parameters: Vec<_> = self
+            .generics
+            .type_params()
+            .map(|parameter| &parameter.ident)
+            .collect();
+
+        if type_parameters.is_empty() {
+            None
+        } else {
+            Some(quote! { std::marker::PhantomData<fn() -> ( #( #type_parameters, )* )> })
+        }
+    }
+
+    /// Create a where clause predicate that requires the `bounded_type` to satisfy the `bound`, but
+    /// that is allowed to not hold.
+    ///
+    /// A bound on a concrete type that doesn't satisfy it is normally rejected by the compiler. The
+    /// higher-ranked lifetime makes the predicate generic, so it is only checked where the
+    /// implementation is used.
+    pub fn trivially_bounded(bounded_type: &Type, bound: TokenStream) -> WherePredicate {
+        parse_quote! {
+            for<'__ezrpc> #bounded_type: #bound
+        }
+    }
+
+    /// Obtain the subset of the generic parameters that are used by the specified `types`.
+    ///
+    /// Only the where clause predicates that exclusively refer to the used parameters are kept.
+    pub fn subset_for<'t>(&self, types: impl IntoIterator<Item = &'t Type>) -> Generics {
+        let names = self.parameter_names();
+        let mut collector = UsedParameters::new(&names);
+
+        for parameter_type in types {
+            collector.visit_type(parameter_type);
+        }
+
+        let used = collector.used;
+
+        let params = self
+            .generics
+            .params
+            .iter()
+            .filter(|parameter| match parameter {
+                GenericParam::Type(type_parameter) => used.contains(&type_parameter.ident),
+                GenericParam::Const(const_parameter) => used.contains(&const_parameter.ident),
+                GenericParam::Lifetime(_) => false,
+            })
+            .cloned()
+            .collect();
+
+        let where_clause = self.generics.where_clause.as_ref().map(|where_clause| {
+            let mut where_clause = where_clause.clone();
+
+            where_clause.predicates = where_clause
+                .predicates
+                .into_iter()
+                .filter(|predicate| {
+                    let mut collector = UsedParameters::new(&names);
+
+                    collector.visit_where_predicate(predicate);
+                    collector.used.is_subset(&used)
+                })
+                .collect();
+
+            where_clause
+        });
+
+        Generics {
+            params,
+            where_clause,
+            ..self.generics.clone()
+        }
+    }
+
+    /// Collect the names of all the type and const parameters.
+    fn parameter_names(&self) -> HashSet<Ident> {
+        let type_parameters = self
+            .generics
+            .type_params()
+            .map(|parameter| parameter.ident.clone());
+        let const_parameters = self
+            .generics
+            .const_params()
+            .map(|parameter| parameter.ident.clone());
+
+        type_parameters.chain(const_parameters).collect()
+    }
+}
+
+/// Syntax tree visitor that collects which generic parameters are used.
+struct UsedParameters<'n> {
+    /// The names of the generic parameters to look for.
+    names: &'n HashSet<Ident>,
+
+    /// The names of the generic parameters that were found.
+    used: HashSet<Ident>,
+}
+
+impl<'n> UsedParameters<'n> {
+    /// Create a new [`UsedParameters`] visitor that looks for the parameters with the specified
+    /// `names`.
+    fn new(names: &'n HashSet<Ident>) -> Self {
+        UsedParameters {
+            names,
+            used: HashSet::new(),
+        }
+    }
+}
+
+impl<'ast, 'n> Visit<'ast> for UsedParameters<'n> {
+    fn visit_path(&mut self, path: &'ast Path) {
+        if path.leading_colon.is_none() {
+            if let Some(first_segment) = path.segments.first() {
+                if self.names.contains(&first_segment.ident) {
+                    self.used.insert(first_segment.ident.clone());
+                }
+            }
+        }
+
+        visit::visit_path(self, path);
+    }
+}
@@ -0,0 +1,198 @@
+use {
+    proc_macro_error::abort,
+    syn::{
+        parse::{Parse, ParseStream},
+        Attribute, Ident, ImplItem, Item, LitStr, Token, TraitItem, Type,
+    },
+};
+
+/// The options set through `#[ezrpc(...)]` attributes on a method.
+#[derive(Default)]
+pub struct MethodAttributes {
+    /// If the method should be left out of the RPC interface.
+    skip: bool,
+
+    /// The name to use for the method's `Request` variant instead of the derived name.
+    rename: Option<Ident>,
+
+    /// The error type of the method's `Result` type alias.
+    error: Option<Type>,
+
+    /// The method's `#[cfg(...)]` and `#[doc = "..."]` attributes, which are forwarded to the
+    /// generated items related to the method.
+    forwarded: Vec<Attribute>,
+}
+
+impl MethodAttributes {
+    /// Create a new [`MethodAttributes`] by parsing the `#[ezrpc(...)]` attributes in the list of
+    /// a method's [`Attribute`]s.
+    ///
+    /// The method's `#[cfg(...)]` and `#[doc = "..."]` attributes are also collected, so that they
+    /// can be forwarded.
+    pub fn new(attributes: &[Attribute]) -> Self {
+        let mut method_attributes = MethodAttributes {
+            forwarded: attributes
+                .iter()
+                .filter(|attribute| Self::is_forwarded(attribute))
+                .cloned()
+                .collect(),
+            ..MethodAttributes::default()
+        };
+
+        for attribute in attributes
+            .iter()
+            .filter(|attribute| Self::is_ezrpc(attribute))
+        {
+            let parsed = match attribute.parse_args::<MethodAttributes>() {
+                Ok(parsed) => parsed,
+                Err(error) => abort!(error.span(), "{}", error),
+            };
+
+            method_attributes.skip |= parsed.skip;
+
+            if parsed.rename.is_some() {
+                method_attributes.rename = parsed.rename;
+            }
+
+            if parsed.error.is_some() {
+                method_attributes.error = parsed.error;
+            }
+        }
+
+        method_attributes
+    }
+
+    /// Parse the error type declared with an `#[ezrpc(error = ...)]` attribute on the `impl`
+    /// block or trait.
+    ///
+    /// The declared error type is used by all methods that don't declare their own. Other options
+    /// can only be used on methods.
+    pub fn item_error(attributes: &[Attribute]) -> Option<Type> {
+        let mut error = None;
+
+        for attribute in attributes
+            .iter()
+            .filter(|attribute| Self::is_ezrpc(attribute))
+        {
+            let parsed = match attribute.parse_args::<MethodAttributes>() {
+                Ok(parsed) => parsed,
+                Err(error) => abort!(error.span(), "{}", error),
+            };
+
+            if parsed.skip || parsed.rename.is_some() {
+                abort!(
+                    attribute,
+                    "Only the `error` option can be used on `impl` blocks and traits"
+                );
+            }
+
+            if parsed.error.is_some() {
+                error = parsed.error;
+            }
+        }
+
+        error
+    }
+
+    /// Check if the method should be left out of the RPC interface.
+    pub fn skip(&self) -> bool {
+        self.skip
+    }
+
+    /// Retrieve the name to use for the method's `Request` variant, if one was set.
+    pub fn rename(&self) -> Option<&Ident> {
+        self.rename.as_ref()
+    }
+
+    /// Retrieve the error type of the method's `Result` type alias, if one was set.
+    pub fn error(&self) -> Option<&Type> {
+        self.error.as_ref()
+    }
+
+    /// Retrieve the method's attributes that should be forwarded to the generated items.
+    pub fn forwarded(&self) -> &[Attribute] {
+        &self.forwarded
+    }
+
+    /// Remove all `#[ezrpc(...)]` attributes from an `impl` block or trait and from its methods.
+    ///
+    /// The attributes are only used by the macro, so they must not be present in the re-emitted
+    /// item.
+    pub fn strip(item: &mut Item) {
+        match item {
+            Item::Impl(item_impl) => {
+                Self::strip_attributes(&mut item_impl.attrs);
+
+                for impl_item in &mut item_impl.items {
+                    if let ImplItem::Method(method) = impl_item {
+                        Self::strip_attributes(&mut method.attrs);
+                    }
+                }
+            }
+            Item::Trait(item_trait) => {
+                Self::strip_attributes(&mut item_trait.attrs);
+
+                for trait_item in &mut item_trait.items {
+                    if let TraitItem::Method(method) = trait_item {
+                        Self::strip_attributes(&mut method.attrs);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Remove all `#[ezrpc(...)]` attributes from a list of [`Attribute`]s.
+    fn strip_attributes(attributes: &mut Vec<Attribute>) {
+        attributes.retain(|attribute| !Self::is_ezrpc(attribute));
+    }
+
+    /// Check if an [`Attribute`] is an `#[ezrpc(...)]` attribute.
+    fn is_ezrpc(attribute: &Attribute) -> bool {
+        attribute.path.is_ident("ezrpc")
+    }
+
+    /// Check if an [`Attribute`] is a `#[cfg(...)]` or a `#[doc = "..."]` attribute.
+    fn is_forwarded(attribute: &Attribute) -> bool {
+        attribute.path.is_ident("cfg") || attribute.path.is_ident("doc")
+    }
+}
+
+impl Parse for MethodAttributes {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut attributes = MethodAttributes::default();
+
+        while !input.is_empty() {
+            let name: Ident = input.parse()?;
+
+            match name.to_string().as_str() {
+                "skip" => attributes.skip = true,
+                "rename" => {
+                    input.parse::<Token![=]>()?;
+
+                    let new_name: LitStr = input.parse()?;
+
+                    attributes.rename = Some(new_name.parse().map_err(|_| {
+                        syn::Error::new(new_name.span(), "The new name must be a valid identifier")
+                    })?);
+                }
+                "error" => {
+                    input.parse::<Token![=]>()?;
+                    attributes.error = Some(input.parse()?);
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        format!("Unknown `{}` option", name),
+                    ))
+                }
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(attributes)
+    }
+}
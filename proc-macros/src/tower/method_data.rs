@@ -1,12 +1,17 @@
 use {
     super::{
-        parameter_data::ParameterData, receiver_type::ReceiverType, response_data::ResponseData,
+        arguments::{Concurrency, MacroArguments},
+        method_attributes::MethodAttributes,
+        parameter_data::ParameterData,
+        receiver_type::ReceiverType,
+        response_data::ResponseData,
         result_data::ResultData,
     },
     heck::CamelCase,
     proc_macro2::TokenStream,
-    quote::quote,
-    syn::{FnArg, Ident, ImplItemMethod, Type},
+    proc_macro_error::abort,
+    quote::{quote, quote_spanned},
+    syn::{Attribute, FnArg, Generics, Ident, Path, Signature, Type},
 };
 
 /// Representation of a method's metadata.
@@ -17,12 +22,20 @@ pub struct MethodData {
     /// The name of the method.
     name: Ident,
 
+    /// The method's `#[cfg(...)]` and `#[doc = "..."]` attributes, which are forwarded to the
+    /// generated items related to the method.
+    forwarded_attributes: Vec<Attribute>,
+
+    /// The lifetime parameters of the method.
+    generics: Generics,
+
     /// The receiver type of the method.
     receiver_type: ReceiverType,
 
     /// The name of the generated `Request` variant.
     ///
-    /// This is equivalent to the name of the method converted to CamelCase.
+    /// This is equivalent to the name of the method converted to CamelCase, unless it was renamed
+    /// with an `#[ezrpc(rename = "...")]` attribute.
     request_name: Ident,
 
     /// The parameters of the method.
@@ -32,40 +45,123 @@ pub struct MethodData {
 
     /// The resulting output of the method.
     result: ResultData,
+
+    /// The path to the variant of the generated `Error` enum that wraps this method's error, if
+    /// the enum is generated and the method returns a [`Result`].
+    error_variant: Option<Path>,
 }
 
 impl MethodData {
-    /// Create a new [`MethodData`] by parsing an [`ImplItemMethod`] syntax tree.
-    pub fn new(method: &ImplItemMethod) -> Self {
-        let asynchronous = method.sig.asyncness.is_some();
-        let receiver_type = ReceiverType::new(&method.sig.inputs);
-        let name = method.sig.ident.clone();
-        let request_name_string = name.to_string().to_camel_case();
-        let request_name = Ident::new(&request_name_string, name.span());
-
-        let parameters = method
-            .sig
+    /// Names of local bindings used in the generated code, which method parameters can't use.
+    pub const RESERVED_NAMES: &'static [&'static str] = &["inner", "service"];
+
+    /// Create a new [`MethodData`] by parsing a method's [`Signature`] syntax tree and its
+    /// [`MethodAttributes`].
+    ///
+    /// The `item_error` is the error type declared for the `impl` block or trait, which is used if
+    /// the method doesn't declare its own.
+    pub fn new(
+        signature: &Signature,
+        attributes: &MethodAttributes,
+        item_error: Option<&Type>,
+    ) -> Self {
+        if let Some(parameter) = signature.generics.type_params().next() {
+            abort!(
+                parameter,
+                "Methods with generic type parameters aren't supported"
+            );
+        }
+
+        if let Some(parameter) = signature.generics.const_params().next() {
+            abort!(
+                parameter,
+                "Methods with generic const parameters aren't supported"
+            );
+        }
+
+        let asynchronous = signature.asyncness.is_some();
+        let receiver_type = ReceiverType::new(&signature.inputs);
+        let name = signature.ident.clone();
+        let generics = signature.generics.clone();
+        let request_name = match attributes.rename() {
+            Some(new_name) => new_name.clone(),
+            None => {
+                let request_name_string = name.to_string().to_camel_case();
+
+                Ident::new(&request_name_string, name.span())
+            }
+        };
+
+        let typed_parameters: Vec<_> = signature
             .inputs
             .iter()
+            .filter(|argument| !ReceiverType::is_receiver(argument))
             .filter_map(|argument| match argument {
                 FnArg::Receiver(_) => None,
                 FnArg::Typed(parameter) => Some(parameter),
             })
-            .map(ParameterData::new)
             .collect();
 
-        let result = ResultData::new(&method.sig.output);
+        let used_names: Vec<_> = typed_parameters
+            .iter()
+            .filter_map(|parameter| ParameterData::identifier(&parameter.pat))
+            .collect();
+
+        let parameters = typed_parameters
+            .into_iter()
+            .enumerate()
+            .map(|(index, parameter)| ParameterData::new(parameter, index, &used_names))
+            .collect();
+
+        let result = ResultData::new(&signature.output, attributes.error(), item_error);
+
+        if attributes.error().is_some() && result.err_type().is_none() {
+            abort!(
+                signature.output,
+                "The `error` option requires the method to return a `Result` type alias"
+            );
+        }
 
         MethodData {
             asynchronous,
             name,
+            forwarded_attributes: attributes.forwarded().to_vec(),
+            generics,
             receiver_type,
             request_name,
             parameters,
             result,
+            error_variant: None,
         }
     }
 
+    /// Retrieve the name of this method.
+    pub fn name(&self) -> &Ident {
+        &self.name
+    }
+
+    /// Check if this method is an asynchronous method.
+    pub fn is_asynchronous(&self) -> bool {
+        self.asynchronous
+    }
+
+    /// Retrieve the method's `#[cfg(...)]` and `#[doc = "..."]` attributes.
+    ///
+    /// These are placed on the generated items that declare something for this method.
+    pub fn forwarded_attributes(&self) -> &[Attribute] {
+        &self.forwarded_attributes
+    }
+
+    /// Retrieve the method's `#[cfg(...)]` attributes.
+    ///
+    /// These are placed on the generated code that uses something for this method, where
+    /// documentation isn't allowed.
+    pub fn cfg_attributes(&self) -> impl Iterator<Item = &Attribute> {
+        self.forwarded_attributes
+            .iter()
+            .filter(|attribute| attribute.path.is_ident("cfg"))
+    }
+
     /// Retrieve the [`ReceiverType`] of this method.
     pub fn receiver_type(&self) -> ReceiverType {
         self.receiver_type
@@ -76,21 +172,44 @@ impl MethodData {
         &self.request_name
     }
 
+    /// Retrieve the names of this method's parameters.
+    pub fn parameter_names(&self) -> impl Iterator<Item = &Ident> {
+        self.parameters.iter().map(ParameterData::name)
+    }
+
+    /// Retrieve the types of this method's parameter fields in the `Request` variant.
+    pub fn field_types(&self) -> impl Iterator<Item = &Type> {
+        self.parameters.iter().map(ParameterData::field_type)
+    }
+
     /// Retrieve the [`ResultData`] of this method.
     pub fn result(&self) -> &ResultData {
         &self.result
     }
 
+    /// Retrieve the path to the variant of the generated `Error` enum that wraps this method's
+    /// error, if there is one.
+    pub fn error_variant(&self) -> Option<&Path> {
+        self.error_variant.as_ref()
+    }
+
+    /// Set the path to the variant of the generated `Error` enum that wraps this method's error.
+    pub fn set_error_variant(&mut self, error_variant: Path) {
+        self.error_variant = Some(error_variant);
+    }
+
     /// Generate the declaration of the `Request` enum variant related to this method.
     pub fn request_enum_variant(&self) -> TokenStream {
         let name = &self.request_name;
+        let attributes = &self.forwarded_attributes;
 
         if self.parameters.is_empty() {
-            quote! { #name }
+            quote! { #( #attributes )* #name }
         } else {
-            let parameters = self.parameters.iter().map(ParameterData::declaration);
+            let parameters = self.parameters.iter().map(ParameterData::field_declaration);
 
             quote! {
+                #( #attributes )*
                 #name {
                     #( #parameters ),*
                 }
@@ -107,13 +226,18 @@ impl MethodData {
         service_receiver_type: ReceiverType,
         self_type: &Type,
         response_data: &ResponseData,
+        arguments: &MacroArguments,
     ) -> TokenStream {
+        let request = arguments.request();
         let request_name = &self.request_name;
-        let method_call = self.method_call(service_receiver_type, self_type, response_data);
+        let method_call =
+            self.method_call(service_receiver_type, self_type, response_data, arguments);
+        let attributes = self.cfg_attributes();
 
         if self.parameters.is_empty() {
             quote! {
-                Request::#request_name => {
+                #( #attributes )*
+                #request::#request_name => {
                     #method_call
                 }
             }
@@ -121,7 +245,8 @@ impl MethodData {
             let bindings = self.bindings();
 
             quote! {
-                Request::#request_name { #bindings } => {
+                #( #attributes )*
+                #request::#request_name { #bindings } => {
                     #method_call
                 }
             }
@@ -134,24 +259,35 @@ impl MethodData {
         service_receiver_type: ReceiverType,
         self_type: &Type,
         response_data: &ResponseData,
+        arguments: &MacroArguments,
     ) -> TokenStream {
-        let method_call_await = self.method_call_await(service_receiver_type, self_type);
+        let method_call_await = self.method_call_await(service_receiver_type, self_type, arguments);
 
-        response_data.conversion_to_response(self, method_call_await)
+        response_data.conversion_to_response(self, method_call_await, arguments.response())
     }
 
     /// Generate the code that calls this method and awaits its result if necessary.
+    ///
+    /// Unless the service is `local`, the method's future is checked to be [`Send`], so that the
+    /// error is reported on the method if it isn't.
     fn method_call_await(
         &self,
         service_receiver_type: ReceiverType,
         self_type: &Type,
+        arguments: &MacroArguments,
     ) -> TokenStream {
-        let method_call = self.raw_method_call(service_receiver_type, self_type);
+        let method_call = self.raw_method_call(service_receiver_type, self_type, arguments);
 
-        if self.asynchronous {
+        if !self.asynchronous {
+            method_call
+        } else if arguments.is_local() {
             quote! { #method_call.await }
         } else {
-            method_call
+            quote_spanned! {self.name.span()=>
+                match #method_call {
+                    future => __ezrpc_assert_send(future).await,
+                }
+            }
         }
     }
 
@@ -160,47 +296,169 @@ impl MethodData {
         &self,
         service_receiver_type: ReceiverType,
         self_type: &Type,
+        arguments: &MacroArguments,
     ) -> TokenStream {
-        let prefix =
-            service_receiver_type.service_method_call_prefix(self.receiver_type, self_type);
+        let prefix = match arguments.concurrency() {
+            Concurrency::Lock => {
+                service_receiver_type.service_method_call_prefix(self.receiver_type, self_type)
+            }
+            Concurrency::Actor => self.receiver_type.actor_method_call_prefix(self_type),
+        };
         let method_name = &self.name;
-        let arguments = self.bindings();
+        let method_arguments = self.parameters.iter().map(ParameterData::argument);
 
-        quote! { #prefix #method_name( #arguments ) }
+        quote! { #prefix #method_name( #( #method_arguments ),* ) }
     }
 
     /// Generate a helper method to create and send the `Request` to call this method's
     /// implementation.
-    pub fn service_method(&self) -> TokenStream {
+    ///
+    /// The helper returns the method's return type if the response type is shared, or a `Result`
+    /// with a `CallError` otherwise. With `concurrency = actor`, the `Service` reports a stopped
+    /// actor as a `CallError`, so the helper is the same as the `RemoteClient`'s.
+    pub fn service_method(
+        &self,
+        response_data: &ResponseData,
+        arguments: &MacroArguments,
+    ) -> TokenStream {
+        if arguments.concurrency() == Concurrency::Actor {
+            return self.remote_client_method(response_data, arguments);
+        }
+
+        let result = response_data.helper_result_type(self, arguments.crate_path());
+        let ready_failure = quote! { panic!("Generated service is always ready") };
+
+        self.helper_method(result, ready_failure, arguments, |call| {
+            response_data.conversion_to_helper_result(
+                self,
+                call,
+                arguments.response(),
+                arguments.crate_path(),
+            )
+        })
+    }
+
+    /// Generate a helper method to create and send the `Request` to call this method on a remote
+    /// service through the generated `RemoteClient`.
+    ///
+    /// The helper returns a `Result` with a `CallError`, so that connection failures can be
+    /// reported.
+    pub fn remote_client_method(
+        &self,
+        response_data: &ResponseData,
+        arguments: &MacroArguments,
+    ) -> TokenStream {
+        let crate_path = arguments.crate_path();
+        let result = response_data.remote_helper_result_type(self, crate_path);
+        let ready_failure = quote! { return Err(#crate_path::CallError::Unavailable) };
+
+        self.helper_method(result, ready_failure, arguments, |call| {
+            response_data.conversion_to_remote_helper_result(
+                self,
+                call,
+                arguments.response(),
+                arguments.crate_path(),
+            )
+        })
+    }
+
+    /// Generate a helper method that waits for the [`tower::Service`] to be ready and calls it
+    /// with the `Request` for this method.
+    ///
+    /// The `ready_failure` is the expression used if the [`tower::Service`] fails to become ready.
+    /// The `response_conversion` receives the expression for the call and generates the
+    /// conversion of its output into the helper's `result` type.
+    fn helper_method(
+        &self,
+        result: TokenStream,
+        ready_failure: TokenStream,
+        arguments: &MacroArguments,
+        response_conversion: impl FnOnce(TokenStream) -> TokenStream,
+    ) -> TokenStream {
         let method_name = &self.name;
+        let attributes = &self.forwarded_attributes;
+        let (generics, _, where_clause) = self.generics.split_for_impl();
         let parameters = self.parameters.iter().map(ParameterData::declaration);
-        let result = &self.result;
-        let request = self.request_construction();
-        let response_conversion = self.result.conversion_from_result();
+        let request = self.request_construction(arguments);
+        let private = arguments.private_module();
+        let response_conversion =
+            response_conversion(quote! { #private::tower::Service::call(service, #request).await });
 
         quote! {
-            pub async fn #method_name(&mut self, #( #parameters ),*) -> #result {
-                use tower::{Service as _, ServiceExt as _};
+            #( #attributes )*
+            pub async fn #method_name #generics(&mut self, #( #parameters ),*) -> #result
+            #where_clause
+            {
+                let service = match #private::tower::ServiceExt::ready(self).await {
+                    Ok(service) => service,
+                    Err(_) => #ready_failure,
+                };
+
+                #response_conversion
+            }
+        }
+    }
 
-                let service = self.ready().await.expect("Generated service is always ready");
+    /// Generate the implementation of this method for the generated `Client` type.
+    ///
+    /// The method sends the `Request` for this method to the wrapped service and extracts the
+    /// result from the response. The service's error is converted into a `CallError`, and failures
+    /// other than the method's own errors are converted into the method's error type.
+    pub fn client_method(
+        &self,
+        response_data: &ResponseData,
+        arguments: &MacroArguments,
+    ) -> TokenStream {
+        let method_name = &self.name;
+        let attributes = &self.forwarded_attributes;
+        let (generics, _, where_clause) = self.generics.split_for_impl();
+        let receiver = self.receiver_type.receiver();
+        let parameters = self.parameters.iter().map(ParameterData::declaration);
+        let result = &self.result;
+        let request = self.request_construction(arguments);
+        let crate_path = arguments.crate_path();
+        let response_conversion = response_data.conversion_to_client_result(
+            self,
+            quote! { result },
+            arguments.response(),
+            crate_path,
+        );
+        let private = arguments.private_module();
+        let error = response_data.err_type();
 
-                service.call(#request).await #response_conversion
+        quote! {
+            #( #attributes )*
+            async fn #method_name #generics(#receiver, #( #parameters ),*) -> #result
+            #where_clause
+            {
+                let mut service = self.0.clone();
+                let result = match #private::tower::ServiceExt::ready(&mut service).await {
+                    Ok(service) => #private::tower::Service::call(service, #request).await,
+                    Err(error) => Err(error),
+                };
+                let result = result.map_err(Into::<#crate_path::CallError<#error>>::into);
+
+                #response_conversion
             }
         }
     }
 
     /// Generate the code to create the `Request` variant for this method.
-    fn request_construction(&self) -> TokenStream {
+    fn request_construction(&self, arguments: &MacroArguments) -> TokenStream {
+        let request = arguments.request();
         let name = &self.request_name;
 
         if self.parameters.is_empty() {
-            quote! { Request::#name }
+            quote! { #request::#name }
         } else {
-            let parameters = self.bindings();
+            let fields = self
+                .parameters
+                .iter()
+                .map(ParameterData::field_initialization);
 
             quote! {
-                Request::#name {
-                    #parameters
+                #request::#name {
+                    #( #fields ),*
                 }
             }
         }
@@ -1,8 +1,17 @@
+mod arguments;
+mod borrowed_type;
+mod error_enum_data;
 mod generator;
+mod generics_data;
+mod method_attributes;
 mod method_data;
 mod parameter_data;
 mod receiver_type;
 mod response_data;
 mod result_data;
+mod trait_data;
 
-pub use self::generator::Generator;
+pub use self::{
+    arguments::MacroArguments, generator::Generator, method_attributes::MethodAttributes,
+    trait_data::TraitData,
+};
@@ -1,43 +1,145 @@
 use {
+    super::borrowed_type::BorrowedType,
     proc_macro2::TokenStream,
+    proc_macro_error::abort,
     quote::quote,
-    syn::{Pat, PatType, Type},
+    syn::{spanned::Spanned, Ident, Pat, PatType, Type},
 };
 
 /// Representation of a function parameter.
 pub struct ParameterData {
-    /// The binding pattern of the parameter.
-    pattern: Pat,
+    /// The name used for the parameter's field in the `Request` variant.
+    ///
+    /// This is the identifier bound by the parameter, without any `mut` or `ref` modifiers. If the
+    /// parameter uses any other pattern (like a tuple pattern or `_`), a synthetic name based on
+    /// the parameter's position is used instead.
+    name: Ident,
     /// The type of the parameter.
     parameter_type: Type,
+
+    /// The borrowed type, if the parameter is a shared reference.
+    ///
+    /// Borrowed parameters are sent as their owned type.
+    borrowed: Option<BorrowedType>,
 }
 
 impl ParameterData {
     /// Create a new [`ParameterData`] from the [`PatType`] parameter syntax tree.
-    pub fn new(parameter: &PatType) -> Self {
+    ///
+    /// The `index` is the position of the parameter in the method's parameter list, excluding the
+    /// receiver, and is used to build a synthetic name if necessary. The synthetic name is chosen
+    /// so that it doesn't clash with any of the `used_names`.
+    pub fn new(parameter: &PatType, index: usize, used_names: &[Ident]) -> Self {
+        let name = Self::identifier(&parameter.pat).unwrap_or_else(|| {
+            let mut synthetic_name = format!("arg{}", index);
+
+            while used_names
+                .iter()
+                .any(|used_name| used_name == &synthetic_name)
+            {
+                synthetic_name.push('_');
+            }
+
+            Ident::new(&synthetic_name, parameter.pat.span())
+        });
+
+        let borrowed = match parameter.ty.as_ref() {
+            Type::Reference(reference) if reference.mutability.is_some() => abort!(
+                reference,
+                "Mutable reference parameters can't be sent in a request"
+            ),
+            Type::Reference(reference) => Some(BorrowedType::new(reference)),
+            _ => None,
+        };
+
         ParameterData {
-            pattern: parameter.pat.as_ref().clone(),
+            name,
             parameter_type: parameter.ty.as_ref().clone(),
+            borrowed,
+        }
+    }
+
+    /// Extract the identifier bound by a parameter pattern, if it is a simple identifier binding.
+    pub fn identifier(pattern: &Pat) -> Option<Ident> {
+        match pattern {
+            Pat::Ident(binding) if binding.subpat.is_none() => Some(binding.ident.clone()),
+            _ => None,
+        }
+    }
+
+    /// Retrieve the name used for this parameter's field.
+    pub fn name(&self) -> &Ident {
+        &self.name
+    }
+
+    /// Retrieve the type of this parameter's field.
+    ///
+    /// This is the parameter type, unless the parameter is borrowed, in which case it is the
+    /// owned type.
+    pub fn field_type(&self) -> &Type {
+        match &self.borrowed {
+            Some(borrowed) => borrowed.owned_type(),
+            None => &self.parameter_type,
         }
     }
 
     /// Obtain the declaration for this parameter.
     ///
-    /// Contains the binding pattern and the parameter type. This can be used when generating a
-    /// matching function parameter or a type field.
+    /// Contains the parameter name and the parameter type. This can be used when generating a
+    /// matching function parameter.
     pub fn declaration(&self) -> TokenStream {
-        let pattern = &self.pattern;
+        let name = &self.name;
         let parameter_type = &self.parameter_type;
 
-        quote! { #pattern: #parameter_type }
+        quote! { #name: #parameter_type }
+    }
+
+    /// Obtain the field declaration for this parameter.
+    ///
+    /// Contains the parameter name and the field type. This can be used when generating a type
+    /// field.
+    pub fn field_declaration(&self) -> TokenStream {
+        let name = &self.name;
+        let field_type = self.field_type();
+
+        quote! { #name: #field_type }
+    }
+
+    /// Obtain the initialization of this parameter's field from the parameter.
+    ///
+    /// Borrowed parameters are converted into their owned type.
+    pub fn field_initialization(&self) -> TokenStream {
+        let name = &self.name;
+
+        match &self.borrowed {
+            Some(_) => {
+                let owned = BorrowedType::to_owned(quote! { #name });
+
+                quote! { #name: #owned }
+            }
+            None => quote! { #name },
+        }
+    }
+
+    /// Obtain the argument to pass this parameter's field to the method implementation.
+    ///
+    /// Borrowed parameters are borrowed back from their owned type.
+    pub fn argument(&self) -> TokenStream {
+        let name = &self.name;
+
+        match &self.borrowed {
+            Some(borrowed) => borrowed.borrow(quote! { #name }),
+            None => quote! { #name },
+        }
     }
 
     /// Obtain the binding used for this parameter.
     ///
-    /// The binding can be used to access the parameter value.
+    /// The binding can be used to access the parameter value. When it is passed to the method
+    /// implementation, the method's original parameter pattern is applied to it.
     pub fn binding(&self) -> TokenStream {
-        let pattern = &self.pattern;
+        let name = &self.name;
 
-        quote! { #pattern }
+        quote! { #name }
     }
 }
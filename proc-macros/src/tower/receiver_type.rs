@@ -2,20 +2,32 @@ use {
     proc_macro2::TokenStream,
     proc_macro_error::abort,
     quote::quote,
-    syn::{FnArg, Type},
+    syn::{FnArg, GenericArgument, Pat, PathArguments, Type},
 };
 
 /// The receiver type of the method.
 ///
-/// Methods with owned receivers aren't represented because they're currently not supported.
+/// The variants are ordered by how strict the access to the shared instance must be, so that the
+/// most strict receiver type can be used to determine how the generated `Service` stores the
+/// instance.
 #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
 pub enum ReceiverType {
     /// A method that has no `self` receiver.
     NoReceiver,
 
+    /// A method that has an owned `self` receiver.
+    ///
+    /// The method is called on a clone of the instance.
+    Owned,
+
     /// A method that has a shared reference `&self` receiver.
     Reference,
 
+    /// A method that has a `self: Arc<Self>` receiver.
+    ///
+    /// The method is called on a clone of the [`Arc`][std::sync::Arc] that holds the instance.
+    Arc,
+
     /// A method that has an exclusive mutable reference `&mut self` receiver.
     MutableReference,
 }
@@ -23,25 +35,95 @@ pub enum ReceiverType {
 impl ReceiverType {
     /// Create a new [`ReceiverType`] from a method's argument list.
     pub fn new<'a>(method_arguments: impl IntoIterator<Item = &'a FnArg>) -> Self {
-        let maybe_receiver = method_arguments
+        method_arguments
             .into_iter()
-            .filter_map(|argument| match argument {
-                FnArg::Receiver(receiver) => Some(receiver),
-                FnArg::Typed(_) => None,
-            })
-            .next();
-
-        if let Some(receiver) = maybe_receiver {
-            if receiver.reference.is_none() {
-                abort!(receiver, "Methods that take ownership aren't supported");
+            .find_map(Self::from_argument)
+            .unwrap_or(ReceiverType::NoReceiver)
+    }
+
+    /// Check if a method argument is the method's receiver.
+    ///
+    /// This includes receivers with an explicit type, like `self: Arc<Self>`.
+    pub fn is_receiver(argument: &FnArg) -> bool {
+        match argument {
+            FnArg::Receiver(_) => true,
+            FnArg::Typed(parameter) => {
+                matches!(parameter.pat.as_ref(), Pat::Ident(binding) if binding.ident == "self")
+            }
+        }
+    }
+
+    /// Create a [`ReceiverType`] from a method argument, if it is a receiver.
+    fn from_argument(argument: &FnArg) -> Option<Self> {
+        match argument {
+            FnArg::Receiver(receiver) => Some(match (&receiver.reference, receiver.mutability) {
+                (None, _) => ReceiverType::Owned,
+                (Some(_), None) => ReceiverType::Reference,
+                (Some(_), Some(_)) => ReceiverType::MutableReference,
+            }),
+            FnArg::Typed(parameter) if Self::is_receiver(argument) => {
+                Some(Self::from_receiver_type(&parameter.ty))
+            }
+            FnArg::Typed(_) => None,
+        }
+    }
+
+    /// Create a [`ReceiverType`] from the explicit type of a receiver.
+    fn from_receiver_type(receiver_type: &Type) -> Self {
+        match receiver_type {
+            Type::Reference(reference) if Self::is_self_type(&reference.elem) => {
+                match reference.mutability {
+                    Some(_) => ReceiverType::MutableReference,
+                    None => ReceiverType::Reference,
+                }
             }
+            Type::Path(_) if Self::is_self_type(receiver_type) => ReceiverType::Owned,
+            Type::Path(path_type) if path_type.qself.is_none() => {
+                let last_segment = path_type
+                    .path
+                    .segments
+                    .last()
+                    .expect("Type path has at least one segment");
 
-            match receiver.mutability {
-                Some(_) => ReceiverType::MutableReference,
-                None => ReceiverType::Reference,
+                let is_arc_of_self = last_segment.ident == "Arc"
+                    && matches!(
+                        &last_segment.arguments,
+                        PathArguments::AngleBracketed(arguments)
+                            if arguments.args.len() == 1
+                                && matches!(
+                                    &arguments.args[0],
+                                    GenericArgument::Type(inner) if Self::is_self_type(inner)
+                                )
+                    );
+
+                if is_arc_of_self {
+                    ReceiverType::Arc
+                } else {
+                    abort!(receiver_type, "Unsupported receiver type");
+                }
             }
-        } else {
-            ReceiverType::NoReceiver
+            _ => abort!(receiver_type, "Unsupported receiver type"),
+        }
+    }
+
+    /// Check if a type is `Self`.
+    fn is_self_type(candidate: &Type) -> bool {
+        match candidate {
+            Type::Path(path_type) => path_type.qself.is_none() && path_type.path.is_ident("Self"),
+            _ => false,
+        }
+    }
+
+    /// Generate the receiver parameter for a method with this [`ReceiverType`].
+    ///
+    /// Methods without a receiver have no receiver parameter, so nothing is generated for them.
+    pub fn receiver(&self) -> TokenStream {
+        match self {
+            ReceiverType::NoReceiver => quote! {},
+            ReceiverType::Owned => quote! { self },
+            ReceiverType::Reference => quote! { &self },
+            ReceiverType::Arc => quote! { self: std::sync::Arc<Self> },
+            ReceiverType::MutableReference => quote! { &mut self },
         }
     }
 
@@ -51,23 +133,39 @@ impl ReceiverType {
     /// for the service, and one that can be more relaxed, for the method to be called. It is
     /// assumed that this method will be called on the more strict receiver type and it will
     /// receive the `method_receiver_type` as an extra parameter.
+    ///
+    /// Methods with owned receivers are called on a clone of the instance. If the instance is
+    /// behind a lock, the lock is released as soon as the clone is made, so it isn't held while
+    /// the method runs.
     pub fn service_method_call_prefix(
         &self,
         method_receiver_type: ReceiverType,
         self_type: &Type,
     ) -> TokenStream {
         match (self, method_receiver_type) {
-            (
-                ReceiverType::NoReceiver | ReceiverType::Reference | ReceiverType::MutableReference,
-                ReceiverType::NoReceiver,
-            ) => {
-                quote! { #self_type:: }
+            (_, ReceiverType::NoReceiver) => {
+                quote! { <#self_type>:: }
             }
 
-            (ReceiverType::Reference, ReceiverType::Reference) => quote! {
+            (
+                ReceiverType::Owned | ReceiverType::Reference | ReceiverType::Arc,
+                ReceiverType::Owned,
+            ) => quote! {
+                <#self_type as Clone>::clone(&inner).
+            },
+
+            (ReceiverType::Reference | ReceiverType::Arc, ReceiverType::Reference)
+            | (ReceiverType::Arc, ReceiverType::Arc) => quote! {
                 inner.
             },
 
+            (ReceiverType::MutableReference, ReceiverType::Owned) => quote! {
+                {
+                    let instance = <#self_type as Clone>::clone(&*inner.read().await);
+                    instance
+                }.
+            },
+
             (ReceiverType::MutableReference, ReceiverType::Reference) => quote! {
                 inner.read().await.
             },
@@ -76,15 +174,43 @@ impl ReceiverType {
                 inner.write().await.
             },
 
+            (ReceiverType::MutableReference, ReceiverType::Arc) => {
+                unreachable!("`self: Arc<Self>` receivers can't be used with `&mut self` receivers")
+            }
+
             (
                 ReceiverType::NoReceiver,
-                ReceiverType::Reference | ReceiverType::MutableReference,
+                ReceiverType::Owned
+                | ReceiverType::Reference
+                | ReceiverType::Arc
+                | ReceiverType::MutableReference,
             )
-            | (ReceiverType::Reference, ReceiverType::MutableReference) => {
+            | (
+                ReceiverType::Owned,
+                ReceiverType::Reference | ReceiverType::Arc | ReceiverType::MutableReference,
+            )
+            | (ReceiverType::Reference, ReceiverType::Arc | ReceiverType::MutableReference)
+            | (ReceiverType::Arc, ReceiverType::MutableReference) => {
                 unreachable!(
                     "Service receiver type should always be stricter than method receiver type"
                 )
             }
         }
     }
+
+    /// Generate the code necessary for calling a method with this [`ReceiverType`] from the
+    /// actor of the generated `Service`.
+    ///
+    /// The actor owns the instance, so methods are called on it directly, except for methods with
+    /// an owned receiver, which are called on a clone of the instance.
+    pub fn actor_method_call_prefix(&self, self_type: &Type) -> TokenStream {
+        match self {
+            ReceiverType::NoReceiver => quote! { <#self_type>:: },
+            ReceiverType::Owned => quote! { <#self_type as Clone>::clone(&inner). },
+            ReceiverType::Reference | ReceiverType::MutableReference => quote! { inner. },
+            ReceiverType::Arc => {
+                unreachable!("`self: Arc<Self>` receivers can't be used with an actor")
+            }
+        }
+    }
 }
@@ -2,100 +2,399 @@ use {
     super::{method_data::MethodData, result_data::ResultData},
     either::Either,
     proc_macro2::TokenStream,
-    quote::{quote, ToTokens},
-    syn::{parse_quote, Ident, Type},
+    quote::{quote, quote_spanned, ToTokens},
+    syn::{parse_quote, spanned::Spanned, Attribute, Generics, Ident, Path, Type},
 };
 
 /// Representation of the RPC response type.
+///
+/// The variants of a generated `Response` enum keep the forwarded attributes of their methods.
 #[derive(Clone)]
 pub enum ResponseData {
     Shared(ResultData),
 
     DisjointWithSharedError {
-        outputs: Vec<(Ident, Box<Type>)>,
+        outputs: Vec<(Ident, Vec<Attribute>, Box<Type>)>,
         error: Box<Type>,
     },
 
-    FullyDisjoint(Vec<(Ident, ResultData)>),
+    FullyDisjoint(Vec<(Ident, Vec<Attribute>, ResultData)>),
 }
 
 impl ResponseData {
     /// Create a new [`ResponseData`] from the list of RPC methods.
-    pub fn new(methods: &[MethodData]) -> Self {
+    ///
+    /// If an `error_type` is specified, it is used as the shared error type, and the methods'
+    /// errors are converted into it.
+    pub fn new(methods: &[MethodData], error_type: Option<Type>) -> Self {
         let method_results = methods.iter().map(MethodData::result);
-        let response_names = methods.iter().map(MethodData::request_name).cloned();
-        let method_ok_types = method_results.clone().map(ResultData::ok_type).cloned();
+        let response_names = methods.iter().map(|method| {
+            (
+                method.request_name().clone(),
+                method.forwarded_attributes().to_vec(),
+            )
+        });
+        let method_ok_types = method_results
+            .clone()
+            .map(|result| Box::new(result.ok_type().clone()));
         let method_err_types = method_results.clone().filter_map(ResultData::err_type);
 
-        if let Some(error) = Self::common_shared_error(method_err_types) {
+        if let Some(error) = error_type {
+            let mut ok_types = method_ok_types.clone();
+            let first_ok_type = ok_types.next().expect("There is at least one method");
+
+            if ok_types.all(|ok_type| ok_type == first_ok_type) {
+                ResponseData::Shared(ResultData::Result {
+                    ok_type: first_ok_type,
+                    err_type: Box::new(error),
+                    borrowed: false,
+                })
+            } else {
+                let outputs = Self::variants(response_names, method_ok_types);
+
+                ResponseData::DisjointWithSharedError {
+                    outputs,
+                    error: Box::new(error),
+                }
+            }
+        } else if let Some(error) = Self::common_shared_error(method_err_types) {
             if let Some(result) = Self::common_shared_result(method_results) {
                 ResponseData::Shared(result)
             } else {
-                let outputs = response_names.zip(method_ok_types).collect();
+                let outputs = Self::variants(response_names, method_ok_types);
 
                 ResponseData::DisjointWithSharedError { outputs, error }
             }
         } else {
-            let results = response_names.zip(method_results.cloned()).collect();
+            let results = Self::variants(response_names, method_results.cloned());
 
             ResponseData::FullyDisjoint(results)
         }
     }
 
-    /// Generate the code for declaring the [`Response`] type, if necessary.
-    pub fn response_type_declaration(&self) -> TokenStream {
+    /// Combine the names and attributes of the `Response` variants with the types they contain.
+    fn variants<T>(
+        names: impl Iterator<Item = (Ident, Vec<Attribute>)>,
+        types: impl Iterator<Item = T>,
+    ) -> Vec<(Ident, Vec<Attribute>, T)> {
+        names
+            .zip(types)
+            .map(|((name, attributes), variant_type)| (name, attributes, variant_type))
+            .collect()
+    }
+
+    /// Generate the code for declaring the `Response` type named `name`, if necessary.
+    ///
+    /// The `attributes` are placed on the declaration.
+    ///
+    /// The `generics` should be the generic parameters used by the [`Self::declared_types`].
+    pub fn response_type_declaration(
+        &self,
+        name: &Ident,
+        generics: &Generics,
+        attributes: &TokenStream,
+    ) -> TokenStream {
         let variants = match self {
             ResponseData::Shared(_) => return quote! {},
             ResponseData::DisjointWithSharedError { outputs, .. } => {
-                Either::Left(outputs.iter().map(|(variant_name, output_type)| {
-                    quote! { #variant_name ( #output_type ) }
-                }))
-            }
-            ResponseData::FullyDisjoint(results) => {
-                Either::Right(results.iter().map(|(variant_name, result_type)| {
-                    quote! { #variant_name ( #result_type ) }
-                }))
+                Either::Left(outputs.iter().map(
+                    |(variant_name, variant_attributes, output_type)| {
+                        quote! { #( #variant_attributes )* #variant_name ( #output_type ) }
+                    },
+                ))
             }
+            ResponseData::FullyDisjoint(results) => Either::Right(results.iter().map(
+                |(variant_name, variant_attributes, result_type)| {
+                    quote! { #( #variant_attributes )* #variant_name ( #result_type ) }
+                },
+            )),
         };
 
+        let (generics, _, where_clause) = generics.split_for_impl();
+
         quote! {
-            pub enum Response {
+            #attributes
+            pub enum #name #generics #where_clause {
                 #( #variants ),*
             }
         }
     }
 
+    /// Return the types used inside the declaration of the `Response` type.
+    pub fn declared_types(&self) -> Vec<&Type> {
+        match self {
+            ResponseData::Shared(_) => vec![],
+            ResponseData::DisjointWithSharedError { outputs, .. } => outputs
+                .iter()
+                .map(|(_, _, output_type)| output_type.as_ref())
+                .collect(),
+            ResponseData::FullyDisjoint(results) => results
+                .iter()
+                .flat_map(|(_, _, result_data)| result_data.ok_type_and_err_type())
+                .collect(),
+        }
+    }
+
     /// Generate the conversion of a method's return type into the response type.
     ///
     /// Wraps the provided `expression` that results in the return type of the `method` into the
-    /// shared response type represented by this [`ResponseData`].
+    /// shared response type represented by this [`ResponseData`], which is named `response`.
     pub fn conversion_to_response(
         &self,
         method: &MethodData,
         expression: TokenStream,
+        response: &Ident,
     ) -> TokenStream {
+        let error_conversion = Self::conversion_to_error_variant(method);
+
         match self {
-            ResponseData::Shared(_) => method.result().conversion_to_result(expression),
+            ResponseData::Shared(_) => {
+                let expression_result = method.result().conversion_to_result(expression);
+
+                quote! { #expression_result #error_conversion }
+            }
             ResponseData::DisjointWithSharedError { .. } => {
                 let variant = method.request_name();
                 let expression_result = method.result().conversion_to_result(expression);
 
-                quote! { #expression_result.map(Response::#variant) }
+                quote! { #expression_result #error_conversion .map(#response::#variant) }
             }
             ResponseData::FullyDisjoint(_) => {
                 let variant = method.request_name();
+                let output = method.result().conversion_to_owned(expression);
+
+                quote! { Ok(#response::#variant(#output)) }
+            }
+        }
+    }
+
+    /// Generate the conversion of a method's error into its variant of the generated `Error`
+    /// enum.
+    ///
+    /// The conversion is applied to a [`Result`] with the method's error, and nothing is
+    /// generated if the `method` has no `Error` variant.
+    fn conversion_to_error_variant(method: &MethodData) -> TokenStream {
+        match method.error_variant() {
+            Some(error_variant) => quote! { .map_err(#error_variant) },
+            None => quote! {},
+        }
+    }
+
+    /// Return the type returned by the `Service` helper method for the `method`.
+    ///
+    /// If the response type is shared, this is the method's return type. Otherwise, it is a
+    /// [`Result`] with the method's output and a `CallError` from the crate at `crate_path`, which
+    /// wraps the shared error type or the method's error type.
+    pub fn helper_result_type(&self, method: &MethodData, crate_path: &Path) -> TokenStream {
+        let result = method.result();
+        let ok_type = result.ok_type();
+
+        match self {
+            ResponseData::Shared(shared_result) if method.error_variant().is_some() => {
+                quote! { #shared_result }
+            }
+            ResponseData::Shared(_) => quote! { #result },
+            ResponseData::DisjointWithSharedError { error, .. } => quote! {
+                Result<#ok_type, #crate_path::CallError<#error>>
+            },
+            ResponseData::FullyDisjoint(_) => {
+                let error = result
+                    .err_type()
+                    .map(ToTokens::to_token_stream)
+                    .unwrap_or_else(|| quote! { std::convert::Infallible });
+
+                quote! { Result<#ok_type, #crate_path::CallError<#error>> }
+            }
+        }
+    }
+
+    /// Generate the conversion of a response into the result of the `Service` helper method for
+    /// the `method`.
+    ///
+    /// Extracts the return value of the `method` from the `expression` that results in the
+    /// [`Result`] returned by the RPC call. The `response` is the name of the generated `Response`
+    /// enum. Failures that can't be represented by the method's return type are reported as a
+    /// `CallError` from the crate at `crate_path`, unless the response type is shared, in which
+    /// case the method's return type is used unchanged.
+    pub fn conversion_to_helper_result(
+        &self,
+        method: &MethodData,
+        expression: TokenStream,
+        response: &Ident,
+        crate_path: &Path,
+    ) -> TokenStream {
+        let variant = method.request_name();
+
+        match self {
+            ResponseData::Shared(_) if method.error_variant().is_some() => expression,
+            ResponseData::Shared(_) => {
+                let result_conversion = method.result().conversion_from_result();
+
+                quote! { #expression #result_conversion }
+            }
+            ResponseData::DisjointWithSharedError { .. } => quote! {
+                match #expression {
+                    Ok(#response::#variant(output)) => Ok(output),
+                    #[allow(unreachable_patterns)]
+                    Ok(_) => Err(#crate_path::CallError::UnexpectedResponse),
+                    Err(error) => Err(#crate_path::CallError::Failed(error)),
+                }
+            },
+            ResponseData::FullyDisjoint(_) => {
+                let output = match method.result().err_type() {
+                    Some(_) => quote! { output.map_err(#crate_path::CallError::Failed) },
+                    None => quote! { Ok(output) },
+                };
+
+                quote! {
+                    match #expression {
+                        Ok(#response::#variant(output)) => #output,
+                        #[allow(unreachable_patterns)]
+                        Ok(_) => Err(#crate_path::CallError::UnexpectedResponse),
+                        Err(()) => Err(#crate_path::CallError::Unavailable),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Return the type returned by the `RemoteClient` helper method for the `method`.
+    ///
+    /// This is a [`Result`] with the method's output and a `CallError` from the crate at
+    /// `crate_path`. The `CallError` wraps the shared error type, or the method's error type if
+    /// the response type is fully disjoint.
+    pub fn remote_helper_result_type(&self, method: &MethodData, crate_path: &Path) -> TokenStream {
+        match self {
+            ResponseData::Shared(_) => {
+                let ok_type = method.result().ok_type();
+                let error = self.err_type();
+
+                quote! { Result<#ok_type, #crate_path::CallError<#error>> }
+            }
+            ResponseData::DisjointWithSharedError { .. } | ResponseData::FullyDisjoint(_) => {
+                self.helper_result_type(method, crate_path)
+            }
+        }
+    }
+
+    /// Generate the conversion of a response into the result of the `RemoteClient` helper method
+    /// for the `method`.
+    ///
+    /// Extracts the return value of the `method` from the `expression` that results in the
+    /// [`Result`] returned by the `RemoteClient`, which already reports errors as a `CallError`
+    /// from the crate at `crate_path`. The `response` is the name of the generated `Response`
+    /// enum.
+    pub fn conversion_to_remote_helper_result(
+        &self,
+        method: &MethodData,
+        expression: TokenStream,
+        response: &Ident,
+        crate_path: &Path,
+    ) -> TokenStream {
+        let variant = method.request_name();
 
-                quote! { Ok(Response::#variant(#expression)) }
+        match self {
+            ResponseData::Shared(_) => expression,
+            ResponseData::DisjointWithSharedError { .. } => quote! {
+                match #expression {
+                    Ok(#response::#variant(output)) => Ok(output),
+                    #[allow(unreachable_patterns)]
+                    Ok(_) => Err(#crate_path::CallError::UnexpectedResponse),
+                    Err(error) => Err(error),
+                }
+            },
+            ResponseData::FullyDisjoint(_) => {
+                let output = match method.result().err_type() {
+                    Some(_) => quote! { output.map_err(#crate_path::CallError::Failed) },
+                    None => quote! { Ok(output) },
+                };
+
+                quote! {
+                    match #expression {
+                        Ok(#response::#variant(output)) => #output,
+                        #[allow(unreachable_patterns)]
+                        Ok(_) => Err(#crate_path::CallError::UnexpectedResponse),
+                        Err(#crate_path::CallError::UnexpectedResponse) => {
+                            Err(#crate_path::CallError::UnexpectedResponse)
+                        }
+                        Err(_) => Err(#crate_path::CallError::Unavailable),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Generate the conversion of a response into a method's return type, for the generated
+    /// `Client`.
+    ///
+    /// The `expression` results in the [`Result`] returned by the wrapped service, with its error
+    /// already converted into a `CallError` from the crate at `crate_path`. The output is
+    /// extracted like in the `RemoteClient` helper methods, and the errors returned by the
+    /// `method` are passed through. Other failures, including errors for other methods, are
+    /// converted into the method's error type `E`, which must implement `From<CallError<E>>`.
+    pub fn conversion_to_client_result(
+        &self,
+        method: &MethodData,
+        expression: TokenStream,
+        response: &Ident,
+        crate_path: &Path,
+    ) -> TokenStream {
+        let output =
+            self.conversion_to_remote_helper_result(method, expression, response, crate_path);
+        let error = method
+            .result()
+            .err_type()
+            .expect("Trait methods return a `Result`");
+        let failure = |call_error: TokenStream| {
+            quote_spanned! {error.span()=>
+                <#error as From<#crate_path::CallError<#error>>>::from(#call_error)
+            }
+        };
+        let unavailable = failure(quote! { #crate_path::CallError::Unavailable });
+        let unexpected_response = failure(quote! { #crate_path::CallError::UnexpectedResponse });
+        let method_error = match method.error_variant() {
+            Some(error_variant) => quote! {
+                match error {
+                    #error_variant(error) => error,
+                    #[allow(unreachable_patterns)]
+                    _ => #unexpected_response,
+                }
+            },
+            None => quote! { error },
+        };
+
+        quote! {
+            #output.map_err(|error| match error {
+                #crate_path::CallError::Failed(error) => #method_error,
+                #crate_path::CallError::Unavailable => #unavailable,
+                #crate_path::CallError::UnexpectedResponse => #unexpected_response,
+            })
+        }
+    }
+
+    /// Return the types used by the [`Ok`][Result::Ok] and [`Err`][Result::Err] types of the RPC
+    /// call.
+    pub fn result_types(&self) -> Vec<&Type> {
+        match self {
+            ResponseData::Shared(result_data) => result_data.ok_type_and_err_type().collect(),
+            ResponseData::DisjointWithSharedError { error, .. } => {
+                let mut types = self.declared_types();
+
+                types.push(error);
+                types
             }
+            ResponseData::FullyDisjoint(_) => self.declared_types(),
         }
     }
 
     /// Return the [`Ok`][Result::Ok] type that's expected from the RPC call.
-    pub fn ok_type(&self) -> TokenStream {
+    ///
+    /// The `response` type is used if the type is the generated `Response` enum.
+    pub fn ok_type(&self, response: &TokenStream) -> TokenStream {
         match self {
             ResponseData::Shared(result_data) => result_data.ok_type().to_token_stream(),
             ResponseData::DisjointWithSharedError { .. } | ResponseData::FullyDisjoint(_) => {
-                quote! { Response }
+                quote! { #response }
             }
         }
     }
@@ -114,7 +413,7 @@ impl ResponseData {
 
     /// Figure out if all methods share a common error type.
     fn common_shared_error<'e>(
-        mut error_types: impl Iterator<Item = &'e Box<Type>>,
+        mut error_types: impl Iterator<Item = &'e Type>,
     ) -> Option<Box<Type>> {
         let first_error_type = match error_types.next() {
             Some(error_type) => error_type,
@@ -123,7 +422,7 @@ impl ResponseData {
 
         error_types
             .all(|error_type| error_type == first_error_type)
-            .then(|| first_error_type.clone())
+            .then(|| Box::new(first_error_type.clone()))
     }
 
     /// Figure out if all methods share a common `Result` type.
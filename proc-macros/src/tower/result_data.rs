@@ -1,6 +1,8 @@
 use {
+    super::borrowed_type::BorrowedType,
     proc_macro2::TokenStream,
     quote::{quote, ToTokens},
+    std::iter,
     syn::{parse_quote, GenericArgument, Path, PathArguments, ReturnType, Type},
 };
 
@@ -8,14 +10,22 @@ use {
 #[derive(Clone, Eq, PartialEq)]
 pub enum ResultData {
     /// The return type is not a [`Result`].
-    NotResult(Box<Type>),
+    ///
+    /// If the return type is a reference, the owned type is stored and `borrowed` is set.
+    NotResult {
+        return_type: Box<Type>,
+        borrowed: bool,
+    },
 
     /// The return type is a [`Result`].
     ///
     /// The [`Ok`][Result::Ok] and [`Err`][Result::Err] types are extracted and stored separately.
+    /// If the [`Ok`][Result::Ok] type is a reference, the owned type is stored and `borrowed` is
+    /// set.
     Result {
         ok_type: Box<Type>,
         err_type: Box<Type>,
+        borrowed: bool,
     },
 }
 
@@ -23,27 +33,82 @@ impl ResultData {
     /// Create a [`ResultData`] from a function's [`ReturnType`].
     ///
     /// Parses the function's return type to try to extract either a [`Result`] type or a non-result
-    /// type. If a [`Result`] type is found (either named `Result` or `std::result::Result`), the
-    /// [`Ok`][Result::Ok] and [`Err`][Result::Err] types are extracted.
+    /// type. If a [`Result`] type is found (either named `Result`, `std::result::Result` or
+    /// `core::result::Result`), the [`Ok`][Result::Ok] and [`Err`][Result::Err] types are
+    /// extracted.
+    ///
+    /// If an error type is declared, type aliases whose name ends with `Result` (like
+    /// `io::Result<T>`, `fmt::Result` or `MyResult<T>`) are also considered [`Result`] types, with
+    /// the declared error as the [`Err`][Result::Err] type unless the alias specifies one. The
+    /// `method_error` is declared for the method itself, so any type with a single type argument
+    /// is considered a [`Result`] alias, while the `item_error` is declared for all methods, and
+    /// so other types like `Vec<T>` are left unchanged.
     ///
     /// For function's that have no return type, the type is set to [`()`].
-    pub fn new(return_type: &ReturnType) -> Self {
+    ///
+    /// References are replaced by their owned types, so that the returned value can be sent in a
+    /// response.
+    pub fn new(
+        return_type: &ReturnType,
+        method_error: Option<&Type>,
+        item_error: Option<&Type>,
+    ) -> Self {
+        let declared_error = match (method_error, item_error) {
+            (Some(error), _) => Some((error, true)),
+            (None, Some(error)) => Some((error, false)),
+            (None, None) => None,
+        };
+
         match return_type {
-            ReturnType::Default => ResultData::NotResult(Box::new(parse_quote! { () })),
+            ReturnType::Default => ResultData::NotResult {
+                return_type: Box::new(parse_quote! { () }),
+                borrowed: false,
+            },
             ReturnType::Type(_, actual_return_type) => {
-                Self::parse_actual_return_type(actual_return_type)
+                Self::parse_actual_return_type(actual_return_type, declared_error)
             }
         }
     }
 
+    /// Replace a reference type with its owned type.
+    ///
+    /// Returns the resulting type and whether it was a reference.
+    fn owned(candidate: Type) -> (Box<Type>, bool) {
+        match candidate {
+            Type::Reference(reference) => (
+                Box::new(BorrowedType::new(&reference).owned_type().clone()),
+                true,
+            ),
+            other => (Box::new(other), false),
+        }
+    }
+
     /// Creates the [`ResultData`] from the extracted [`Type`].
-    fn parse_actual_return_type(return_type: &Type) -> Self {
+    ///
+    /// The `declared_error` is the declared error type, and whether it was declared for the method
+    /// itself.
+    fn parse_actual_return_type(return_type: &Type, declared_error: Option<(&Type, bool)>) -> Self {
         match return_type {
             Type::Path(path_type) if path_type.qself.is_none() => {
                 Self::extract_result_type(&path_type.path)
-                    .unwrap_or_else(|| ResultData::NotResult(Box::new(return_type.clone())))
+                    .or_else(|| {
+                        declared_error.and_then(|(error, any_alias)| {
+                            Self::extract_result_alias_type(&path_type.path, error, any_alias)
+                        })
+                    })
+                    .unwrap_or_else(|| Self::not_result(return_type.clone()))
             }
-            other => ResultData::NotResult(Box::new(other.clone())),
+            other => Self::not_result(other.clone()),
+        }
+    }
+
+    /// Creates a [`ResultData::NotResult`] for the extracted [`Type`].
+    fn not_result(return_type: Type) -> Self {
+        let (return_type, borrowed) = Self::owned(return_type);
+
+        ResultData::NotResult {
+            return_type,
+            borrowed,
         }
     }
 
@@ -70,14 +135,63 @@ impl ResultData {
             _ => return None,
         };
 
+        let (ok_type, borrowed) = Self::owned(ok_type);
+
         Some(ResultData::Result {
-            ok_type: Box::new(ok_type),
+            ok_type,
             err_type: Box::new(err_type),
+            borrowed,
         })
     }
 
-    /// Attempts to extract the type arguments inside a [`Path`] that is either `Result` or
-    /// `std::result::Result`.
+    /// Attempts to create the [`ResultData`] from the [`Path`] of a type alias whose name ends with
+    /// `Result`, or of any type with a single type argument if `any_alias` is set.
+    ///
+    /// The alias's type arguments are used as the [`Ok`][Result::Ok] type and the
+    /// [`Err`][Result::Err] type. If the alias doesn't specify the [`Err`][Result::Err] type, the
+    /// `declared_error` is used, and if it also doesn't specify the [`Ok`][Result::Ok] type, [`()`]
+    /// is used.
+    fn extract_result_alias_type(
+        path: &Path,
+        declared_error: &Type,
+        any_alias: bool,
+    ) -> Option<Self> {
+        let last_segment = path.segments.last()?;
+        let named_result = last_segment.ident.to_string().ends_with("Result");
+
+        let generic_types: Vec<_> = match &last_segment.arguments {
+            PathArguments::None => vec![],
+            PathArguments::AngleBracketed(arguments) => arguments
+                .args
+                .iter()
+                .map(|argument| match argument {
+                    GenericArgument::Type(argument_type) => Some(argument_type.clone()),
+                    _ => None,
+                })
+                .collect::<Option<_>>()?,
+            PathArguments::Parenthesized(_) => return None,
+        };
+
+        let (ok_type, err_type) = match generic_types.as_slice() {
+            [] if named_result => (parse_quote! { () }, declared_error.clone()),
+            [ok_type] if named_result || any_alias => (ok_type.clone(), declared_error.clone()),
+            [ok_type, err_type] if named_result => (ok_type.clone(), err_type.clone()),
+            _ => return None,
+        };
+
+        let (ok_type, borrowed) = Self::owned(ok_type);
+
+        Some(ResultData::Result {
+            ok_type,
+            err_type: Box::new(err_type),
+            borrowed,
+        })
+    }
+
+    /// Attempts to extract the type arguments inside a [`Path`] that is either `Result`,
+    /// `std::result::Result` or `core::result::Result`.
+    ///
+    /// The full paths are also recognized with a leading `::`.
     fn extract_result_type_arguments(path: &Path) -> Option<&PathArguments> {
         let mut segments = path.segments.iter();
         let first_segment = segments.next()?;
@@ -88,9 +202,10 @@ impl ResultData {
             let second_segment = segments.next()?;
             let third_segment = segments.next()?;
 
-            if first_segment.ident == "std"
+            if (first_segment.ident == "std" || first_segment.ident == "core")
                 && second_segment.ident == "result"
                 && third_segment.ident == "Result"
+                && segments.next().is_none()
             {
                 Some(&third_segment.arguments)
             } else {
@@ -100,30 +215,64 @@ impl ResultData {
     }
 
     /// Returns the [`Ok`][Result::Ok] type, or the bare return type if it's not a [`Result`] type.
-    pub fn ok_type(&self) -> &Box<Type> {
+    pub fn ok_type(&self) -> &Type {
         match self {
-            ResultData::NotResult(return_type) => return_type,
+            ResultData::NotResult { return_type, .. } => return_type,
             ResultData::Result { ok_type, .. } => ok_type,
         }
     }
 
     /// Returns the [`Err`][Result::Err] type if the return type is a [`Result`] type.
-    pub fn err_type(&self) -> Option<&Box<Type>> {
+    pub fn err_type(&self) -> Option<&Type> {
         match self {
-            ResultData::NotResult(_) => None,
+            ResultData::NotResult { .. } => None,
             ResultData::Result { err_type, .. } => Some(err_type),
         }
     }
 
-    /// Returns the code to convert an expression that results in an instance of this
-    /// [`ResultData`] type into a [`Result`].
+    /// Check if the return type is a reference, or a [`Result`] with a reference as the
+    /// [`Ok`][Result::Ok] type.
+    pub fn is_borrowed(&self) -> bool {
+        match self {
+            ResultData::NotResult { borrowed, .. } | ResultData::Result { borrowed, .. } => {
+                *borrowed
+            }
+        }
+    }
+
+    /// Returns the [`Ok`][Result::Ok] type and the [`Err`][Result::Err] type if there is one.
+    pub fn ok_type_and_err_type(&self) -> impl Iterator<Item = &Type> {
+        iter::once(self.ok_type()).chain(self.err_type())
+    }
+
+    /// Returns the code to convert an expression that results in the method's return type into
+    /// this [`ResultData`] type.
+    ///
+    /// Returned references are converted into their owned types, and the expression is left
+    /// unchanged otherwise.
+    pub fn conversion_to_owned(&self, expression: TokenStream) -> TokenStream {
+        match self {
+            ResultData::NotResult { borrowed: true, .. } => BorrowedType::to_owned(expression),
+            ResultData::Result { borrowed: true, .. } => {
+                let to_owned = BorrowedType::to_owned(quote! { output });
+
+                quote! { #expression.map(|output| #to_owned) }
+            }
+            ResultData::NotResult { .. } | ResultData::Result { .. } => expression,
+        }
+    }
+
+    /// Returns the code to convert an expression that results in the method's return type into a
+    /// [`Result`].
     ///
     /// The conversion is either simple the expression or the expression wrapped inside an
-    /// [`Ok`][Result::Ok] variant.
+    /// [`Ok`][Result::Ok] variant, after converting returned references into their owned types.
     pub fn conversion_to_result(&self, expression: TokenStream) -> TokenStream {
+        let owned = self.conversion_to_owned(expression);
+
         match self {
-            ResultData::NotResult(_) => quote! { Ok(#expression) },
-            ResultData::Result { .. } => expression,
+            ResultData::NotResult { .. } => quote! { Ok(#owned) },
+            ResultData::Result { .. } => owned,
         }
     }
 
@@ -133,7 +282,7 @@ impl ResultData {
     /// the [`Result`], so it may panic.
     pub fn conversion_from_result(&self) -> TokenStream {
         match self {
-            ResultData::NotResult(_) => quote! { .expect("Result data never fails") },
+            ResultData::NotResult { .. } => quote! { .expect("Result data never fails") },
             ResultData::Result { .. } => quote! {},
         }
     }
@@ -142,8 +291,10 @@ impl ResultData {
 impl ToTokens for ResultData {
     fn to_tokens(&self, token_stream: &mut TokenStream) {
         match self {
-            ResultData::NotResult(return_type) => return_type.to_tokens(token_stream),
-            ResultData::Result { ok_type, err_type } => {
+            ResultData::NotResult { return_type, .. } => return_type.to_tokens(token_stream),
+            ResultData::Result {
+                ok_type, err_type, ..
+            } => {
                 let result = quote! { ::std::result::Result<#ok_type, #err_type> };
 
                 result.to_tokens(token_stream)
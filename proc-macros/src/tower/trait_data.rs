@@ -0,0 +1,141 @@
+use {
+    proc_macro2::{Span, TokenStream},
+    proc_macro_error::abort,
+    quote::quote,
+    syn::{
+        parse_quote, Block, GenericParam, Generics, Ident, ItemTrait, ReturnType, TraitItem, Type,
+        WherePredicate,
+    },
+};
+
+/// Representation of the trait that defines an RPC interface.
+pub struct TraitData {
+    /// The name of the trait.
+    name: Ident,
+
+    /// The generic parameters of the trait.
+    generics: Generics,
+}
+
+impl TraitData {
+    /// Create a new [`TraitData`] from the [`ItemTrait`] syntax tree.
+    pub fn new(item: &ItemTrait) -> Self {
+        if let Some(item) = item
+            .items
+            .iter()
+            .find(|item| !matches!(item, TraitItem::Method(_)))
+        {
+            abort!(item, "Only methods are supported inside the trait");
+        }
+
+        TraitData {
+            name: item.ident.clone(),
+            generics: item.generics.clone(),
+        }
+    }
+
+    /// Retrieve the path to the trait, including its generic arguments.
+    pub fn path(&self) -> TokenStream {
+        let name = &self.name;
+        let (_, type_generics, _) = self.generics.split_for_impl();
+
+        quote! { #name #type_generics }
+    }
+
+    /// Retrieve the generic parameters of the trait.
+    pub fn generics(&self) -> &Generics {
+        &self.generics
+    }
+
+    /// Create a type parameter name called `name` that does not clash with the trait's generic
+    /// parameters.
+    pub fn unique_parameter(&self, name: &str) -> Ident {
+        let mut name = name.to_owned();
+
+        while self
+            .generics
+            .params
+            .iter()
+            .any(|parameter| match parameter {
+                GenericParam::Type(type_parameter) => type_parameter.ident == name,
+                GenericParam::Const(const_parameter) => const_parameter.ident == name,
+                GenericParam::Lifetime(_) => false,
+            })
+        {
+            name.insert(0, '_');
+        }
+
+        Ident::new(&name, Span::call_site())
+    }
+
+    /// Retrieve the type parameter used to represent the type that implements the trait.
+    pub fn implementation_parameter(&self) -> Ident {
+        self.unique_parameter("T")
+    }
+
+    /// Retrieve the type that implements the trait as a [`Type`].
+    pub fn implementation_type(&self) -> Type {
+        let parameter = self.implementation_parameter();
+
+        parse_quote! { #parameter }
+    }
+
+    /// Build the generic parameters for a `Service` that dispatches to the trait's
+    /// implementation.
+    ///
+    /// The trait implementation type parameter is placed first, followed by the trait's generic
+    /// parameters. Unless the service is `local`, the implementation is required to be [`Send`]
+    /// and [`Sync`] so that it can be shared with the returned [`Future`][std::future::Future]s.
+    pub fn service_generics(&self, local: bool) -> Generics {
+        let parameter = self.implementation_parameter();
+        let path = self.path();
+        let mut generics = self.generics.clone();
+
+        generics
+            .params
+            .insert(0, parse_quote! { #parameter: #path });
+
+        if !local {
+            let send_bound: WherePredicate = parse_quote! { #parameter: Send + Sync };
+
+            generics.make_where_clause().predicates.push(send_bound);
+        }
+
+        generics
+    }
+
+    /// Change the trait's asynchronous methods so that they return [`Send`] futures.
+    ///
+    /// The methods are desugared into methods that return `impl Future + Send`, which allows the
+    /// generated `Service` to box the futures into [`Send`] trait objects. Default method bodies
+    /// are wrapped in `async move` blocks.
+    pub fn make_futures_send(item: &mut ItemTrait) {
+        for item in &mut item.items {
+            if let TraitItem::Method(method) = item {
+                if method.sig.asyncness.take().is_none() {
+                    continue;
+                }
+
+                let output = match &method.sig.output {
+                    ReturnType::Default => quote! { () },
+                    ReturnType::Type(_, output) => quote! { #output },
+                };
+
+                method.sig.output = parse_quote! {
+                    -> impl std::future::Future<Output = #output> + Send
+                };
+
+                if let Some(body) = &mut method.default {
+                    let statements = &body.stmts;
+                    let async_body: Block = parse_quote! {
+                        {
+                            async move { #( #statements )* }
+                        }
+                    };
+
+                    *body = async_body;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use {
+    crate::{CallError, Dispatcher, PendingResponse, RequestId},
+    async_oneshot::Sender,
+    futures::{
+        channel::mpsc,
+        future::{self, Either},
+        pin_mut, ready, FutureExt, Sink, SinkExt, Stream, StreamExt,
+    },
+    std::{
+        future::Future,
+        pin::Pin,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+        task::{Context, Poll},
+    },
+};
+
+/// A request waiting to be sent by the background task, with its ID and the endpoint for its
+/// response.
+type QueuedRequest<Request, Response, Error> =
+    (RequestId, Request, Sender<Result<Response, Error>>);
+
+/// A connection to a remote service.
+///
+/// Each request is assigned a [`RequestId`] and sent through a transport [`Sink`] as a
+/// `(RequestId, Request)` envelope. The remote service replies with
+/// `(RequestId, Result<Response, Error>)` envelopes, which are routed back to the caller by a
+/// [`Dispatcher`].
+///
+/// The transport is driven by a background task, which is returned when the [`Connection`] is
+/// created and must be spawned. The [`Connection`] can be cheaply cloned, and all clones share the
+/// same transport.
+///
+/// Dropping a [`ResponseFuture`] cancels its request, so that the [`Dispatcher`] doesn't keep
+/// waiting for its response. Requests cancelled before they are sent are not sent at all.
+pub struct Connection<Request, Response, Error> {
+    requests: mpsc::Sender<QueuedRequest<Request, Response, Error>>,
+    next_id: Arc<AtomicU64>,
+    dispatcher: Dispatcher<RequestId, Result<Response, Error>>,
+}
+
+impl<Request, Response, Error> Connection<Request, Response, Error> {
+    /// The number of requests that can wait to be sent before callers must wait.
+    const REQUEST_BUFFER_SIZE: usize = 32;
+
+    /// Create a new [`Connection`] that sends requests through the `requests` [`Sink`] and
+    /// receives their responses from the `responses` [`Stream`].
+    ///
+    /// Returns the [`Connection`] and the background task that must be spawned to drive the
+    /// transport. The task finishes when the `responses` [`Stream`] ends or fails, or when all
+    /// clones of the [`Connection`] have been dropped and all responses have been received.
+    pub fn new<Requests, Responses, TransportError>(
+        requests: Requests,
+        responses: Responses,
+    ) -> (Self, impl Future<Output = ()>)
+    where
+        Requests: Sink<(RequestId, Request)>,
+        Responses: Stream<Item = Result<(RequestId, Result<Response, Error>), TransportError>>,
+    {
+        let (sender, receiver) = mpsc::channel(Self::REQUEST_BUFFER_SIZE);
+        let dispatcher = Dispatcher::new();
+        let writer = Self::write_requests(receiver, requests, dispatcher.clone());
+        let reader = Self::read_responses(responses, dispatcher.clone());
+
+        let task = async move {
+            pin_mut!(writer, reader);
+
+            if let Either::Left(((), reader)) = future::select(writer, reader).await {
+                reader.await;
+            }
+        };
+
+        let connection = Connection {
+            requests: sender,
+            next_id: Arc::new(AtomicU64::new(0)),
+            dispatcher,
+        };
+
+        (connection, task)
+    }
+
+    /// Register the response endpoint of each queued request in the `dispatcher` and send the
+    /// request through the `requests` [`Sink`].
+    ///
+    /// Requests that were cancelled while queued are skipped. If a request can't be sent, it is
+    /// removed from the `dispatcher` so that its caller fails instead of waiting for a response,
+    /// and no more requests are sent. The `requests` [`Sink`] is closed once all clones of the
+    /// [`Connection`] have been dropped.
+    async fn write_requests<Requests>(
+        mut queue: mpsc::Receiver<QueuedRequest<Request, Response, Error>>,
+        requests: Requests,
+        mut dispatcher: Dispatcher<RequestId, Result<Response, Error>>,
+    ) where
+        Requests: Sink<(RequestId, Request)>,
+    {
+        pin_mut!(requests);
+
+        while let Some((id, request, sender)) = queue.next().await {
+            if sender.is_closed() {
+                continue;
+            }
+
+            if dispatcher.send((id, sender)).await.is_err() {
+                return;
+            }
+
+            if requests.send((id, request)).await.is_err() {
+                dispatcher.remove(&id);
+                return;
+            }
+        }
+
+        let _ = requests.close().await;
+    }
+
+    /// Route the responses received from the `responses` [`Stream`] to their callers.
+    ///
+    /// Once the `responses` [`Stream`] ends or fails, the `dispatcher` is closed so that the
+    /// callers still waiting for a response fail instead of waiting forever.
+    async fn read_responses<Responses, TransportError>(
+        responses: Responses,
+        mut dispatcher: Dispatcher<RequestId, Result<Response, Error>>,
+    ) where
+        Responses: Stream<Item = Result<(RequestId, Result<Response, Error>), TransportError>>,
+    {
+        pin_mut!(responses);
+
+        while let Some(Ok((id, response))) = responses.next().await {
+            if dispatcher.send((id, response)).await.is_err() {
+                return;
+            }
+        }
+
+        let _ = SinkExt::<(RequestId, Result<Response, Error>)>::close(&mut dispatcher).await;
+    }
+}
+
+impl<Request, Response, Error> Clone for Connection<Request, Response, Error> {
+    fn clone(&self) -> Self {
+        Connection {
+            requests: self.requests.clone(),
+            next_id: self.next_id.clone(),
+            dispatcher: self.dispatcher.clone(),
+        }
+    }
+}
+
+/// Send requests to the remote service.
+///
+/// Errors returned by the remote service are reported as [`CallError::Failed`], and failures of
+/// the connection are reported as [`CallError::Unavailable`].
+impl<Request, Response, Error> tower::Service<Request> for Connection<Request, Response, Error> {
+    type Response = Response;
+    type Error = CallError<Error>;
+    type Future = ResponseFuture<Response, Error>;
+
+    fn poll_ready(&mut self, context: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.requests
+            .poll_ready(context)
+            .map_err(|_| CallError::Unavailable)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = self.dispatcher.cancellable_oneshot(id);
+        let receiver = self
+            .requests
+            .start_send((id, request, sender))
+            .ok()
+            .map(|()| receiver);
+
+        ResponseFuture { receiver }
+    }
+}
+
+/// The [`Future`] for the response of a request sent through a [`Connection`].
+///
+/// Dropping it before the response is received cancels the request.
+pub struct ResponseFuture<Response, Error> {
+    receiver: Option<PendingResponse<RequestId, Result<Response, Error>>>,
+}
+
+impl<Response, Error> Future for ResponseFuture<Response, Error> {
+    type Output = Result<Response, CallError<Error>>;
+
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        let receiver = match self.receiver.as_mut() {
+            Some(receiver) => receiver,
+            None => return Poll::Ready(Err(CallError::Unavailable)),
+        };
+
+        let result = ready!(receiver.poll_unpin(context));
+
+        self.receiver = None;
+
+        Poll::Ready(match result {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(error)) => Err(CallError::Failed(error)),
+            Err(_) => Err(CallError::Unavailable),
+        })
+    }
+}
@@ -0,0 +1,5 @@
+//! Clients that send requests to a remote service.
+
+mod connection;
+
+pub use self::connection::{Connection, ResponseFuture};
@@ -0,0 +1,62 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    io,
+};
+
+/// An error while sending or receiving framed messages.
+#[derive(Debug)]
+pub enum CodecError {
+    /// The transport failed.
+    Io(io::Error),
+
+    /// A frame is larger than the maximum allowed frame size.
+    FrameTooLarge {
+        /// The size of the frame, in bytes.
+        size: usize,
+
+        /// The maximum allowed frame size, in bytes.
+        max_size: usize,
+    },
+
+    /// A frame is too short to contain a request ID.
+    FrameTooShort,
+
+    /// The payload couldn't be serialized or deserialized.
+    Serialization(Box<dyn Error + Send + Sync>),
+}
+
+impl Display for CodecError {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::Io(error) => write!(formatter, "Transport failed: {}", error),
+            CodecError::FrameTooLarge { size, max_size } => write!(
+                formatter,
+                "Frame of {} bytes exceeds the maximum of {} bytes",
+                size, max_size
+            ),
+            CodecError::FrameTooShort => {
+                write!(formatter, "Frame is too short to contain a request ID")
+            }
+            CodecError::Serialization(error) => {
+                write!(formatter, "Failed to (de)serialize payload: {}", error)
+            }
+        }
+    }
+}
+
+impl Error for CodecError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CodecError::Io(error) => Some(error),
+            CodecError::Serialization(error) => Some(error.as_ref()),
+            CodecError::FrameTooLarge { .. } | CodecError::FrameTooShort => None,
+        }
+    }
+}
+
+impl From<io::Error> for CodecError {
+    fn from(error: io::Error) -> Self {
+        CodecError::Io(error)
+    }
+}
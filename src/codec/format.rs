@@ -0,0 +1,67 @@
+use {
+    super::CodecError,
+    serde::{de::DeserializeOwned, Serialize},
+};
+
+/// A serialization format for the payload of the frames.
+pub trait Format {
+    /// Serialize the `value` into bytes.
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, CodecError>;
+
+    /// Deserialize a value from the `bytes`.
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, CodecError>;
+}
+
+/// The [`bincode`] binary format.
+///
+/// The format isn't self-describing, so it can only be used with the default `external` tagging
+/// of the generated enums.
+#[cfg(feature = "bincode")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Bincode;
+
+#[cfg(feature = "bincode")]
+impl Format for Bincode {
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, CodecError> {
+        bincode::serialize(value).map_err(|error| CodecError::Serialization(error))
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, CodecError> {
+        bincode::deserialize(bytes).map_err(|error| CodecError::Serialization(error))
+    }
+}
+
+/// The JSON format, using [`serde_json`].
+#[cfg(feature = "json")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Json;
+
+#[cfg(feature = "json")]
+impl Format for Json {
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, CodecError> {
+        serde_json::to_vec(value).map_err(|error| CodecError::Serialization(error.into()))
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, CodecError> {
+        serde_json::from_slice(bytes).map_err(|error| CodecError::Serialization(error.into()))
+    }
+}
+
+/// The MessagePack format, using [`rmp_serde`].
+///
+/// Structs are serialized as maps, so that internally and adjacently tagged enums can be
+/// deserialized.
+#[cfg(feature = "msgpack")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MessagePack;
+
+#[cfg(feature = "msgpack")]
+impl Format for MessagePack {
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, CodecError> {
+        rmp_serde::to_vec_named(value).map_err(|error| CodecError::Serialization(error.into()))
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, CodecError> {
+        rmp_serde::from_slice(bytes).map_err(|error| CodecError::Serialization(error.into()))
+    }
+}
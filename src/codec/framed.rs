@@ -0,0 +1,538 @@
+use {
+    super::{CodecError, Format},
+    crate::RequestId,
+    futures::{
+        io::{AsyncRead, AsyncWrite},
+        ready, Sink, Stream,
+    },
+    serde::{de::DeserializeOwned, Serialize},
+    std::{
+        convert::TryInto,
+        io,
+        marker::PhantomData,
+        pin::Pin,
+        task::{Context, Poll},
+    },
+};
+
+/// The size of the frame length prefix, in bytes.
+const LENGTH_SIZE: usize = 4;
+
+/// The size of the request ID at the start of each frame, in bytes.
+const ID_SIZE: usize = 8;
+
+/// The number of bytes to attempt to read from the transport at a time.
+const READ_CHUNK_SIZE: usize = 8 * 1024;
+
+/// The number of buffered bytes after which the buffer is written out before accepting more
+/// frames.
+const WRITE_BUFFER_LIMIT: usize = 8 * 1024;
+
+/// A transport adapter that sends and receives `(RequestId, payload)` envelopes in
+/// length-delimited frames.
+///
+/// Each frame starts with the length of the rest of the frame as a 32-bit big-endian integer,
+/// followed by the [`RequestId`] as a 64-bit big-endian integer and the payload serialized with
+/// the [`Format`].
+///
+/// Received envelopes with `Incoming` payloads are available through the [`Stream`]
+/// implementation, and envelopes with `Outgoing` payloads are sent through the [`Sink`]
+/// implementation. Frames larger than the maximum frame size are rejected in both directions,
+/// without buffering the received frame.
+///
+/// A received frame whose payload can't be deserialized is reported as an error and skipped, so
+/// the following frames can still be received. Any other error while receiving, like an invalid
+/// length prefix or a transport failure, ends the [`Stream`] after it is reported.
+///
+/// # Example
+///
+/// A server receives `Request`s and sends the results of the calls, so its `Outgoing` payload is
+/// a [`Result`] with the `Response` and the `Service`'s error:
+///
+/// ```
+/// # #[derive(serde::Deserialize, serde::Serialize)]
+/// # pub struct EmptyString;
+/// # #[derive(serde::Deserialize, serde::Serialize)]
+/// # pub struct TooLong;
+/// # pub struct Example;
+/// # #[ezrpc::tower(module = example_rpc, serde, error_enum)]
+/// # impl Example {
+/// #     pub fn echo(&self, string: String) -> Result<String, EmptyString> {
+/// #         Ok(string)
+/// #     }
+/// #     pub fn length(&self, string: String) -> Result<usize, TooLong> {
+/// #         Ok(string.len())
+/// #     }
+/// # }
+/// # #[cfg(feature = "json")]
+/// # fn main() {
+/// # let connection = futures::io::Cursor::new(Vec::new());
+/// use ezrpc::codec::{Framed, Json};
+///
+/// let framed: Framed<
+///     _,
+///     _,
+///     example_rpc::Request,
+///     Result<example_rpc::Response, example_rpc::Error>,
+/// > = Framed::new(connection, Json).with_max_frame_size(64 * 1024);
+///
+/// let server = ezrpc::server::serve(example_rpc::Service::new(Example), framed);
+/// # drop(server);
+/// # }
+/// # #[cfg(not(feature = "json"))]
+/// # fn main() {}
+/// ```
+pub struct Framed<Transport, F, Incoming, Outgoing> {
+    transport: Transport,
+    format: F,
+    max_frame_size: usize,
+    read_buffer: Vec<u8>,
+    write_buffer: Vec<u8>,
+    written_bytes: usize,
+    read_failed: bool,
+    _payloads: PhantomData<fn(Outgoing) -> Incoming>,
+}
+
+impl<Transport, F, Incoming, Outgoing> Framed<Transport, F, Incoming, Outgoing> {
+    /// The default maximum frame size, in bytes.
+    pub const DEFAULT_MAX_FRAME_SIZE: usize = 8 * 1024 * 1024;
+
+    /// Create a new [`Framed`] adapter that sends and receives frames over the `transport`, with
+    /// payloads serialized using the `format`.
+    pub fn new(transport: Transport, format: F) -> Self {
+        Framed {
+            transport,
+            format,
+            max_frame_size: Self::DEFAULT_MAX_FRAME_SIZE,
+            read_buffer: Vec::new(),
+            write_buffer: Vec::new(),
+            written_bytes: 0,
+            read_failed: false,
+            _payloads: PhantomData,
+        }
+    }
+
+    /// Change the maximum size of a frame, in bytes.
+    ///
+    /// The size doesn't include the length prefix, but includes the request ID.
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    /// Retrieve the maximum size of a frame, in bytes.
+    pub fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+
+    /// Retrieve a reference to the underlying transport.
+    pub fn get_ref(&self) -> &Transport {
+        &self.transport
+    }
+
+    /// Retrieve a mutable reference to the underlying transport.
+    ///
+    /// Reading from or writing to the transport directly may corrupt the frames.
+    pub fn get_mut(&mut self) -> &mut Transport {
+        &mut self.transport
+    }
+
+    /// Consume the adapter and return the underlying transport.
+    ///
+    /// Any buffered data is lost.
+    pub fn into_inner(self) -> Transport {
+        self.transport
+    }
+}
+
+impl<Transport, F, Incoming, Outgoing> Framed<Transport, F, Incoming, Outgoing>
+where
+    F: Format,
+    Incoming: DeserializeOwned,
+{
+    /// Decode the first frame in the read buffer, if it has been fully received.
+    fn decode_frame(&mut self) -> Result<Option<(RequestId, Incoming)>, CodecError> {
+        if self.read_buffer.len() < LENGTH_SIZE {
+            return Ok(None);
+        }
+
+        let length_bytes = self.read_buffer[..LENGTH_SIZE]
+            .try_into()
+            .expect("Slice has the size of the length prefix");
+        let frame_size = u32::from_be_bytes(length_bytes) as usize;
+
+        if frame_size > self.max_frame_size {
+            return Err(CodecError::FrameTooLarge {
+                size: frame_size,
+                max_size: self.max_frame_size,
+            });
+        } else if frame_size < ID_SIZE {
+            return Err(CodecError::FrameTooShort);
+        }
+
+        let payload_start = LENGTH_SIZE + ID_SIZE;
+        let frame_end = LENGTH_SIZE + frame_size;
+
+        if self.read_buffer.len() < frame_end {
+            return Ok(None);
+        }
+
+        let id_bytes = self.read_buffer[LENGTH_SIZE..payload_start]
+            .try_into()
+            .expect("Slice has the size of a request ID");
+        let id = RequestId::from_be_bytes(id_bytes);
+        let payload = self
+            .format
+            .deserialize(&self.read_buffer[payload_start..frame_end]);
+
+        self.read_buffer.drain(..frame_end);
+
+        Ok(Some((id, payload?)))
+    }
+
+    /// Stop receiving frames after a framing or transport `error`, because the position of the
+    /// next frame is unknown.
+    fn fail_reading(&mut self, error: CodecError) -> CodecError {
+        self.read_failed = true;
+        self.read_buffer = Vec::new();
+
+        error
+    }
+}
+
+impl<Transport, F, Incoming, Outgoing> Framed<Transport, F, Incoming, Outgoing>
+where
+    Transport: AsyncWrite + Unpin,
+{
+    /// Write all buffered frames to the transport.
+    fn poll_write_buffer(&mut self, context: &mut Context<'_>) -> Poll<Result<(), CodecError>> {
+        while self.written_bytes < self.write_buffer.len() {
+            let bytes_written = ready!(Pin::new(&mut self.transport)
+                .poll_write(context, &self.write_buffer[self.written_bytes..]))?;
+
+            if bytes_written == 0 {
+                return Poll::Ready(Err(io::Error::from(io::ErrorKind::WriteZero).into()));
+            }
+
+            self.written_bytes += bytes_written;
+        }
+
+        self.write_buffer.clear();
+        self.written_bytes = 0;
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// The adapter never pins the transport or the format.
+impl<Transport, F, Incoming, Outgoing> Unpin for Framed<Transport, F, Incoming, Outgoing> where
+    Transport: Unpin
+{
+}
+
+impl<Transport, F, Incoming, Outgoing> Stream for Framed<Transport, F, Incoming, Outgoing>
+where
+    Transport: AsyncRead + Unpin,
+    F: Format,
+    Incoming: DeserializeOwned,
+{
+    type Item = Result<(RequestId, Incoming), CodecError>;
+
+    fn poll_next(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if this.read_failed {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            match this.decode_frame() {
+                Ok(Some(envelope)) => return Poll::Ready(Some(Ok(envelope))),
+                Ok(None) => {}
+                Err(error @ CodecError::Serialization(_)) => return Poll::Ready(Some(Err(error))),
+                Err(error) => return Poll::Ready(Some(Err(this.fail_reading(error)))),
+            }
+
+            let start = this.read_buffer.len();
+
+            this.read_buffer.resize(start + READ_CHUNK_SIZE, 0);
+
+            let read_result =
+                Pin::new(&mut this.transport).poll_read(context, &mut this.read_buffer[start..]);
+            let bytes_read = match read_result {
+                Poll::Ready(Ok(bytes_read)) => bytes_read,
+                Poll::Ready(Err(error)) => {
+                    return Poll::Ready(Some(Err(this.fail_reading(error.into()))));
+                }
+                Poll::Pending => {
+                    this.read_buffer.truncate(start);
+                    return Poll::Pending;
+                }
+            };
+
+            this.read_buffer.truncate(start + bytes_read);
+
+            if bytes_read == 0 {
+                if this.read_buffer.is_empty() {
+                    return Poll::Ready(None);
+                } else {
+                    let error = io::Error::from(io::ErrorKind::UnexpectedEof);
+
+                    return Poll::Ready(Some(Err(this.fail_reading(error.into()))));
+                }
+            }
+        }
+    }
+}
+
+impl<Transport, F, Incoming, Outgoing> Sink<(RequestId, Outgoing)>
+    for Framed<Transport, F, Incoming, Outgoing>
+where
+    Transport: AsyncWrite + Unpin,
+    F: Format,
+    Outgoing: Serialize,
+{
+    type Error = CodecError;
+
+    fn poll_ready(
+        self: Pin<&mut Self>,
+        context: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+
+        if this.write_buffer.len() >= WRITE_BUFFER_LIMIT {
+            ready!(this.poll_write_buffer(context))?;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: (RequestId, Outgoing)) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        let (id, payload) = item;
+        let payload = this.format.serialize(&payload)?;
+        let frame_size = ID_SIZE + payload.len();
+        let too_large = CodecError::FrameTooLarge {
+            size: frame_size,
+            max_size: this.max_frame_size,
+        };
+
+        if frame_size > this.max_frame_size {
+            return Err(too_large);
+        }
+
+        let length: u32 = frame_size.try_into().map_err(|_| too_large)?;
+
+        this.write_buffer.extend_from_slice(&length.to_be_bytes());
+        this.write_buffer.extend_from_slice(&id.to_be_bytes());
+        this.write_buffer.extend_from_slice(&payload);
+
+        Ok(())
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        context: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+
+        ready!(this.poll_write_buffer(context))?;
+        ready!(Pin::new(&mut this.transport).poll_flush(context))?;
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+        mut self: Pin<&mut Self>,
+        context: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        ready!(self.as_mut().poll_flush(context))?;
+        ready!(Pin::new(&mut self.get_mut().transport).poll_close(context))?;
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use {
+        super::*,
+        crate::codec::Json,
+        futures::{executor, io::Cursor, SinkExt, StreamExt},
+        std::collections::VecDeque,
+    };
+
+    /// A transport that returns its data in fixed chunks, with a pending read between them.
+    struct ChunkedReader {
+        chunks: VecDeque<Vec<u8>>,
+        ready: bool,
+    }
+
+    impl ChunkedReader {
+        fn new(bytes: &[u8], chunk_size: usize) -> Self {
+            ChunkedReader {
+                chunks: bytes.chunks(chunk_size).map(<[u8]>::to_vec).collect(),
+                ready: false,
+            }
+        }
+    }
+
+    impl AsyncRead for ChunkedReader {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            context: &mut Context<'_>,
+            buffer: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            if !self.ready {
+                self.ready = true;
+                context.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+
+            self.ready = false;
+
+            let chunk = match self.chunks.pop_front() {
+                Some(chunk) => chunk,
+                None => return Poll::Ready(Ok(0)),
+            };
+
+            assert!(chunk.len() <= buffer.len());
+            buffer[..chunk.len()].copy_from_slice(&chunk);
+
+            Poll::Ready(Ok(chunk.len()))
+        }
+    }
+
+    /// Encode the envelopes into the bytes sent over the transport.
+    fn encode(envelopes: Vec<(RequestId, String)>) -> Vec<u8> {
+        let mut framed: Framed<_, _, (), String> = Framed::new(Cursor::new(Vec::new()), Json);
+
+        executor::block_on(async {
+            for envelope in envelopes {
+                framed.feed(envelope).await.unwrap();
+            }
+
+            framed.flush().await.unwrap();
+        });
+
+        framed.into_inner().into_inner()
+    }
+
+    /// Decode all envelopes received from the `transport`.
+    fn decode(
+        transport: impl AsyncRead + Unpin,
+        max_frame_size: usize,
+    ) -> Vec<Result<(RequestId, String), CodecError>> {
+        let framed: Framed<_, _, String, ()> =
+            Framed::new(transport, Json).with_max_frame_size(max_frame_size);
+
+        executor::block_on(framed.collect())
+    }
+
+    #[test]
+    fn frame_has_big_endian_length_and_id_before_payload() {
+        let bytes = encode(vec![(0x0102_0304_0506_0708, "hi".to_owned())]);
+
+        assert_eq!(
+            bytes,
+            [&[0, 0, 0, 12][..], &[1, 2, 3, 4, 5, 6, 7, 8], b"\"hi\"",].concat()
+        );
+    }
+
+    #[test]
+    fn frames_are_decoded_into_envelopes() {
+        let bytes = encode(vec![
+            (1, "first".to_owned()),
+            (u64::MAX, "second".to_owned()),
+        ]);
+        let envelopes = decode(Cursor::new(bytes), 1024);
+
+        assert_eq!(envelopes.len(), 2);
+        assert!(matches!(&envelopes[0], Ok((1, payload)) if payload == "first"));
+        assert!(matches!(&envelopes[1], Ok((u64::MAX, payload)) if payload == "second"));
+    }
+
+    #[test]
+    fn frames_split_across_reads_are_reassembled() {
+        let bytes = encode(vec![(1, "first".to_owned()), (2, "second".to_owned())]);
+
+        for chunk_size in [1, 3, 13, bytes.len() - 1] {
+            let envelopes = decode(ChunkedReader::new(&bytes, chunk_size), 1024);
+
+            assert_eq!(envelopes.len(), 2, "chunk size {}", chunk_size);
+            assert!(matches!(&envelopes[0], Ok((1, payload)) if payload == "first"));
+            assert!(matches!(&envelopes[1], Ok((2, payload)) if payload == "second"));
+        }
+    }
+
+    #[test]
+    fn oversized_length_prefix_is_rejected_before_the_frame_is_received() {
+        let envelopes = decode(Cursor::new(u32::MAX.to_be_bytes().to_vec()), 1024);
+
+        assert_eq!(envelopes.len(), 1);
+        assert!(matches!(
+            envelopes[0],
+            Err(CodecError::FrameTooLarge {
+                size,
+                max_size: 1024,
+            }) if size == u32::MAX as usize
+        ));
+    }
+
+    #[test]
+    fn length_prefix_without_request_id_is_rejected() {
+        let bytes = [
+            &[0, 0, 0, 4, 0, 0, 0, 0][..],
+            &encode(vec![(1, "next".to_owned())]),
+        ]
+        .concat();
+        let envelopes = decode(Cursor::new(bytes), 1024);
+
+        assert_eq!(envelopes.len(), 1);
+        assert!(matches!(envelopes[0], Err(CodecError::FrameTooShort)));
+    }
+
+    #[test]
+    fn frame_with_invalid_payload_is_skipped() {
+        let invalid_frame = [&[0, 0, 0, 9][..], &7_u64.to_be_bytes(), b"5"].concat();
+        let bytes = [invalid_frame, encode(vec![(8, "valid".to_owned())])].concat();
+        let envelopes = decode(Cursor::new(bytes), 1024);
+
+        assert_eq!(envelopes.len(), 2);
+        assert!(matches!(envelopes[0], Err(CodecError::Serialization(_))));
+        assert!(matches!(&envelopes[1], Ok((8, payload)) if payload == "valid"));
+    }
+
+    #[test]
+    fn oversized_payload_is_not_sent() {
+        let mut framed: Framed<_, _, (), String> =
+            Framed::new(Cursor::new(Vec::new()), Json).with_max_frame_size(ID_SIZE + 4);
+
+        executor::block_on(async {
+            framed.send((1, "hi".to_owned())).await.unwrap();
+
+            assert!(matches!(
+                framed.send((2, "too long".to_owned())).await,
+                Err(CodecError::FrameTooLarge {
+                    size: 18,
+                    max_size: 12,
+                })
+            ));
+        });
+
+        assert_eq!(framed.into_inner().into_inner().len(), LENGTH_SIZE + 12);
+    }
+
+    #[test]
+    fn truncated_stream_fails_with_unexpected_eof() {
+        let bytes = encode(vec![(1, "first".to_owned()), (2, "second".to_owned())]);
+        let envelopes = decode(Cursor::new(bytes[..bytes.len() - 1].to_vec()), 1024);
+
+        assert_eq!(envelopes.len(), 2);
+        assert!(matches!(&envelopes[0], Ok((1, payload)) if payload == "first"));
+        assert!(matches!(
+            &envelopes[1],
+            Err(CodecError::Io(error)) if error.kind() == io::ErrorKind::UnexpectedEof
+        ));
+    }
+}
@@ -0,0 +1,24 @@
+//! Framing and serialization of requests and responses sent over a byte stream.
+//!
+//! Each message is sent as a length-delimited frame that carries a [`RequestId`] and the
+//! serialized payload. The [`Framed`] adapter converts any
+//! [`AsyncRead`][futures::io::AsyncRead] + [`AsyncWrite`][futures::io::AsyncWrite] transport into
+//! a [`Stream`][futures::Stream] of received envelopes and a [`Sink`][futures::Sink] of envelopes
+//! to send. The payload is serialized with a [`Format`], and the available formats are selected
+//! through the `bincode`, `json` and `msgpack` features.
+
+mod error;
+mod format;
+mod framed;
+
+pub use {
+    self::{error::CodecError, format::Format, framed::Framed},
+    crate::RequestId,
+};
+
+#[cfg(feature = "bincode")]
+pub use self::format::Bincode;
+#[cfg(feature = "json")]
+pub use self::format::Json;
+#[cfg(feature = "msgpack")]
+pub use self::format::MessagePack;
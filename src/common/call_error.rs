@@ -0,0 +1,92 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    io,
+};
+
+/// An error returned by the helper methods of a generated `Service`.
+///
+/// The helper methods send a `Request` to the `Service` and extract the method's output from the
+/// `Response`. This can fail because the method itself failed, or because the `Response` couldn't
+/// be converted into the method's output.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CallError<E> {
+    /// The method returned an error.
+    Failed(E),
+
+    /// The service failed to handle the request, without an error from the method.
+    Unavailable,
+
+    /// The service responded with the output of a different method.
+    UnexpectedResponse,
+}
+
+impl<E> CallError<E> {
+    /// Retrieve the error returned by the method, if there is one.
+    pub fn into_failure(self) -> Option<E> {
+        match self {
+            CallError::Failed(error) => Some(error),
+            CallError::Unavailable | CallError::UnexpectedResponse => None,
+        }
+    }
+}
+
+impl<E> From<E> for CallError<E> {
+    /// Wrap an error returned by the method.
+    fn from(error: E) -> Self {
+        CallError::Failed(error)
+    }
+}
+
+impl From<CallError<String>> for String {
+    /// Extract the error returned by the method, or describe the failure.
+    fn from(error: CallError<String>) -> Self {
+        match error {
+            CallError::Failed(error) => error,
+            CallError::Unavailable | CallError::UnexpectedResponse => error.to_string(),
+        }
+    }
+}
+
+impl From<CallError<io::Error>> for io::Error {
+    /// Extract the error returned by the method, or describe the failure with an
+    /// [`io::ErrorKind::NotConnected`] or [`io::ErrorKind::InvalidData`] error.
+    fn from(error: CallError<io::Error>) -> Self {
+        match error {
+            CallError::Failed(error) => error,
+            CallError::Unavailable => {
+                io::Error::new(io::ErrorKind::NotConnected, error.to_string())
+            }
+            CallError::UnexpectedResponse => {
+                io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+            }
+        }
+    }
+}
+
+impl<E> Display for CallError<E>
+where
+    E: Display,
+{
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CallError::Failed(error) => write!(formatter, "Method failed: {}", error),
+            CallError::Unavailable => write!(formatter, "Service failed to handle the request"),
+            CallError::UnexpectedResponse => {
+                write!(formatter, "Received a response for a different method")
+            }
+        }
+    }
+}
+
+impl<E> Error for CallError<E>
+where
+    E: Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CallError::Failed(error) => Some(error),
+            CallError::Unavailable | CallError::UnexpectedResponse => None,
+        }
+    }
+}
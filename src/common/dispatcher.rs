@@ -1,13 +1,20 @@
 use {
-    crate::util::MutexStateMachine,
+    super::{
+        dispatcher_policy::IgnoredErrorCounters, pending_requests::PendingRequests,
+        pending_response::CancelHook, DispatcherError, DispatcherPolicy, IgnoredErrors,
+        PendingResponse,
+    },
     async_oneshot::Sender,
-    fast_async_mutex::mutex::MutexOwnedGuard,
-    futures::{ready, Sink},
+    futures::{future, pin_mut, Sink},
+    futures_timer::Delay,
     std::{
-        collections::HashMap,
+        fmt::{self, Debug, Formatter},
+        future::Future,
         hash::Hash,
         pin::Pin,
+        sync::{Arc, Mutex, MutexGuard, PoisonError},
         task::{Context, Poll},
+        time::Instant,
     },
 };
 
@@ -23,27 +30,255 @@ use {
 ///
 /// In order to use the [`Dispatcher`] as both types of [`Sink`]s, it can be cheaply cloned, and
 /// one instance can be used for each case, since the data is stored and shared through an
-/// [`Arc`][std::sync::Arc] internally.
-#[derive(Debug)]
+/// [`Arc`][std::sync::Arc] internally. The shared data is only locked briefly while an item is
+/// sent, so a clone that is polled for readiness but never sends an item doesn't block the other
+/// clones.
+///
+/// Pending requests can also be added with a deadline, by sending a tuple of an ID, a [`Sender`]
+/// endpoint and an [`Instant`]. Requests that don't receive their `Response` before their deadline
+/// are resolved by the task returned from [`Dispatcher::expire_requests`].
+///
+/// Requests registered with the [`Sender`] endpoint from [`Dispatcher::cancellable_oneshot`] are
+/// cancelled if their [`PendingResponse`] is dropped before the `Response` is received. Cancelled
+/// requests are removed immediately, and an optional cancel hook is called with their IDs, which
+/// can be used to notify the remote peer.
+///
+/// Closing the [`Sink`] of responses closes the [`Dispatcher`] for all of its clones. All pending
+/// requests are dropped, so their [`Receiver`][async_oneshot::Receiver] endpoints resolve with a
+/// [`Closed`][async_oneshot::Closed] error, and all [`Sink`]s reject any items sent afterwards.
+/// Closing a [`Sink`] of requests only stops that clone from registering requests, and leaves the
+/// pending requests unchanged.
+///
+/// Registering a request with the ID of a pending request is rejected by default, leaving the
+/// pending request unchanged. Responses for unknown IDs, including those of cancelled requests, and
+/// responses for dropped receivers are counted and ignored by default. A [`DispatcherPolicy`] can
+/// be used to change which conditions are reported as [`DispatcherError`]s.
 pub struct Dispatcher<Id, Response> {
-    pending_requests: MutexStateMachine<HashMap<Id, Sender<Response>>>,
-    guard: Option<MutexOwnedGuard<HashMap<Id, Sender<Response>>>>,
+    pending_requests: Arc<Mutex<PendingRequests<Id, Response>>>,
+    cancel_hook: Option<Arc<CancelHook<Id>>>,
+    policy: DispatcherPolicy,
+    ignored_errors: Arc<IgnoredErrorCounters>,
 }
 
 impl<Id, Response> Dispatcher<Id, Response> {
+    /// Create a new [`Dispatcher`] without any pending requests.
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         Dispatcher {
-            pending_requests: MutexStateMachine::new(HashMap::new()),
-            guard: None,
+            pending_requests: Arc::new(Mutex::new(PendingRequests::new())),
+            cancel_hook: None,
+            policy: DispatcherPolicy::default(),
+            ignored_errors: Arc::default(),
+        }
+    }
+
+    /// Create a new [`Dispatcher`] that calls the `hook` with the ID of each cancelled request.
+    ///
+    /// The `hook` is called without holding any locks, so it may use the [`Dispatcher`].
+    pub fn with_cancel_hook(hook: impl Fn(Id) + Send + Sync + 'static) -> Self {
+        Dispatcher {
+            cancel_hook: Some(Arc::new(hook)),
+            ..Dispatcher::new()
+        }
+    }
+
+    /// Change which conditions are reported as [`DispatcherError`]s.
+    ///
+    /// The `policy` only applies to this instance and to clones created from it afterwards.
+    pub fn with_policy(mut self, policy: DispatcherPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Retrieve the number of times each [`DispatcherError`] condition was ignored, by this
+    /// [`Dispatcher`] and all of its clones.
+    pub fn ignored_errors(&self) -> IgnoredErrors {
+        self.ignored_errors.snapshot()
+    }
+
+    /// Report the `error` if the policy requires it, or count it as ignored.
+    fn handle_error<ErrorId>(
+        &self,
+        error: DispatcherError<ErrorId>,
+    ) -> Result<(), DispatcherError<ErrorId>> {
+        if self.policy.is_error(&error) {
+            Err(error)
+        } else {
+            self.ignored_errors.count(&error);
+            Ok(())
+        }
+    }
+}
+
+impl<Id, Response> Dispatcher<Id, Response>
+where
+    Id: Eq + Hash,
+{
+    /// Create the endpoints for a request with the `id` that is cancelled if the
+    /// [`PendingResponse`] is dropped before receiving the `Response`.
+    ///
+    /// The [`Sender`] endpoint must be registered by sending it with the `id` to the
+    /// [`Dispatcher`].
+    pub fn cancellable_oneshot(&self, id: Id) -> (Sender<Response>, PendingResponse<Id, Response>) {
+        let (sender, receiver) = async_oneshot::oneshot();
+        let pending_response = PendingResponse::new(
+            id,
+            receiver,
+            Arc::downgrade(&self.pending_requests),
+            self.cancel_hook.clone(),
+        );
+
+        (sender, pending_response)
+    }
+
+    /// Close the [`Dispatcher`], resolving all pending requests with the `reason` `Response`.
+    ///
+    /// Any items sent to the [`Dispatcher`] afterwards are rejected.
+    pub fn shutdown(&self, reason: Response)
+    where
+        Response: Clone,
+    {
+        if let Some(mut pending_requests) = self.lock_open() {
+            for mut sender in pending_requests.close() {
+                let _ = sender.send(reason.clone());
+            }
+        }
+    }
+
+    /// Remove the pending request with the `id`, without resolving it.
+    ///
+    /// The request's [`PendingResponse`] fails as if its [`Sender`] had been dropped.
+    pub(crate) fn remove(&self, id: &Id) {
+        let sender = self
+            .pending_requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(id);
+
+        drop(sender);
+    }
+
+    /// Return a task that resolves the pending requests with the `timeout` `Response` once their
+    /// deadline has passed.
+    ///
+    /// The task must be spawned for deadlines to have any effect. It uses a single timer for the
+    /// earliest deadline, and finishes once the [`Dispatcher`] is closed. Running more than one
+    /// task is allowed, but unnecessary.
+    pub fn expire_requests(&self, timeout: Response) -> impl Future<Output = ()>
+    where
+        Response: Clone,
+    {
+        let dispatcher = self.clone();
+
+        async move {
+            loop {
+                let (next_deadline, generation) = {
+                    let mut pending_requests = match dispatcher.lock_open() {
+                        Some(pending_requests) => pending_requests,
+                        None => return,
+                    };
+
+                    for mut sender in pending_requests.expire(Instant::now()) {
+                        let _ = sender.send(timeout.clone());
+                    }
+
+                    (
+                        pending_requests.next_deadline(),
+                        pending_requests.timer_generation(),
+                    )
+                };
+
+                let rescheduled =
+                    future::poll_fn(|context| dispatcher.poll_rescheduled(context, generation));
+
+                match next_deadline {
+                    Some(deadline) => {
+                        let delay = Delay::new(deadline.saturating_duration_since(Instant::now()));
+
+                        pin_mut!(rescheduled);
+                        future::select(delay, rescheduled).await;
+                    }
+                    None => rescheduled.await,
+                }
+            }
+        }
+    }
+
+    /// Check if the timers have been rescheduled since the timer `generation`, registering the
+    /// current task to be woken up when they are.
+    fn poll_rescheduled(&self, context: &mut Context<'_>, generation: u64) -> Poll<()> {
+        let mut pending_requests = self
+            .pending_requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if pending_requests.timer_generation() != generation || pending_requests.is_closed() {
+            Poll::Ready(())
+        } else {
+            pending_requests.register_timer(context.waker());
+            Poll::Pending
+        }
+    }
+
+    /// Acquire the pending requests lock.
+    ///
+    /// The lock is only held synchronously, and never across calls to the [`Sink`] methods.
+    /// Returns [`None`] if the [`Dispatcher`] has been closed.
+    fn lock_open(&self) -> Option<MutexGuard<'_, PendingRequests<Id, Response>>> {
+        let pending_requests = self
+            .pending_requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if pending_requests.is_closed() {
+            None
+        } else {
+            Some(pending_requests)
+        }
+    }
+
+    /// Check if an item can be sent.
+    ///
+    /// If the [`Dispatcher`] has been closed, fails unless the policy ignores
+    /// [`DispatcherError::Closed`], in which case the item will be discarded.
+    fn check_ready(&self) -> Result<(), DispatcherError<Id>> {
+        if self.lock_open().is_none() && self.policy.is_error(&DispatcherError::<Id>::Closed) {
+            Err(DispatcherError::Closed)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Close the [`Dispatcher`], dropping all pending requests.
+    fn close(&self) {
+        if let Some(mut pending_requests) = self.lock_open() {
+            pending_requests.close();
         }
     }
 }
 
+impl<Id, Response> Debug for Dispatcher<Id, Response>
+where
+    Id: Debug,
+    Response: Debug,
+{
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("Dispatcher")
+            .field("pending_requests", &self.pending_requests)
+            .field("policy", &self.policy)
+            .field("ignored_errors", &self.ignored_errors)
+            .finish_non_exhaustive()
+    }
+}
+
 impl<Id, Response> Clone for Dispatcher<Id, Response> {
     fn clone(&self) -> Self {
         Dispatcher {
             pending_requests: self.pending_requests.clone(),
-            guard: None,
+            cancel_hook: self.cancel_hook.clone(),
+            policy: self.policy,
+            ignored_errors: self.ignored_errors.clone(),
         }
     }
 }
@@ -52,34 +287,70 @@ impl<Id, Response> Sink<(Id, Sender<Response>)> for Dispatcher<Id, Response>
 where
     Id: Eq + Hash,
 {
-    type Error = ();
+    type Error = DispatcherError<Id>;
 
-    fn poll_ready(
-        mut self: Pin<&mut Self>,
-        context: &mut Context<'_>,
-    ) -> Poll<Result<(), Self::Error>> {
-        let mut this = self.as_mut();
+    fn poll_ready(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(self.check_ready())
+    }
 
-        if this.guard.is_none() {
-            let guard = ready!(this.pending_requests.poll_lock(context));
+    fn start_send(self: Pin<&mut Self>, item: (Id, Sender<Response>)) -> Result<(), Self::Error> {
+        let (id, sender) = item;
+        let mut pending_requests = match self.lock_open() {
+            Some(pending_requests) => pending_requests,
+            None => return self.handle_error(DispatcherError::Closed),
+        };
 
-            this.guard = Some(guard);
+        if pending_requests.contains(&id)
+            && self
+                .handle_error(DispatcherError::DuplicateId(&id))
+                .is_err()
+        {
+            return Err(DispatcherError::DuplicateId(id));
         }
 
+        pending_requests.insert(id, sender);
+
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         Poll::Ready(Ok(()))
     }
+}
+
+impl<Id, Response> Sink<(Id, Sender<Response>, Instant)> for Dispatcher<Id, Response>
+where
+    Id: Clone + Eq + Hash,
+{
+    type Error = DispatcherError<Id>;
+
+    fn poll_ready(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(self.check_ready())
+    }
 
     fn start_send(
-        mut self: Pin<&mut Self>,
-        item: (Id, Sender<Response>),
+        self: Pin<&mut Self>,
+        item: (Id, Sender<Response>, Instant),
     ) -> Result<(), Self::Error> {
-        let (id, sender) = item;
+        let (id, sender, deadline) = item;
+        let mut pending_requests = match self.lock_open() {
+            Some(pending_requests) => pending_requests,
+            None => return self.handle_error(DispatcherError::Closed),
+        };
+
+        if pending_requests.contains(&id)
+            && self
+                .handle_error(DispatcherError::DuplicateId(&id))
+                .is_err()
+        {
+            return Err(DispatcherError::DuplicateId(id));
+        }
 
-        self.as_mut()
-            .guard
-            .take()
-            .expect("Attempt to send item without holding the pending requests lock")
-            .insert(id, sender);
+        pending_requests.insert_with_deadline(id, sender, deadline);
 
         Ok(())
     }
@@ -97,35 +368,26 @@ impl<Id, Response> Sink<(Id, Response)> for Dispatcher<Id, Response>
 where
     Id: Eq + Hash,
 {
-    type Error = ();
-
-    fn poll_ready(
-        mut self: Pin<&mut Self>,
-        context: &mut Context<'_>,
-    ) -> Poll<Result<(), Self::Error>> {
-        let mut this = self.as_mut();
-
-        if this.guard.is_none() {
-            let guard = ready!(this.pending_requests.poll_lock(context));
+    type Error = DispatcherError<Id>;
 
-            this.guard = Some(guard);
-        }
-
-        Poll::Ready(Ok(()))
+    fn poll_ready(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(self.check_ready())
     }
 
-    fn start_send(mut self: Pin<&mut Self>, item: (Id, Response)) -> Result<(), Self::Error> {
+    fn start_send(self: Pin<&mut Self>, item: (Id, Response)) -> Result<(), Self::Error> {
         let (id, response) = item;
+        let maybe_sender = match self.lock_open() {
+            Some(mut pending_requests) => pending_requests.remove(&id),
+            None => return self.handle_error(DispatcherError::Closed),
+        };
 
-        let maybe_sender = self
-            .as_mut()
-            .guard
-            .take()
-            .expect("Attempt to send item without holding the pending requests lock")
-            .remove(&id);
-
-        if let Some(mut sender) = maybe_sender {
-            let _ = sender.send(response);
+        match maybe_sender {
+            Some(mut sender) => {
+                if sender.send(response).is_err() {
+                    self.handle_error(DispatcherError::ReceiverDropped(id))?;
+                }
+            }
+            None => self.handle_error(DispatcherError::UnknownId(id))?,
         }
 
         Ok(())
@@ -136,6 +398,8 @@ where
     }
 
     fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.close();
+
         Poll::Ready(Ok(()))
     }
 }
@@ -0,0 +1,51 @@
+use std::{
+    error::Error,
+    fmt::{self, Debug, Display, Formatter},
+};
+
+/// An error returned by the [`Sink`][futures::Sink]s of a [`Dispatcher`][super::Dispatcher].
+///
+/// Which of these conditions are reported as errors, and which are only counted and ignored, is
+/// configured with a [`DispatcherPolicy`][super::DispatcherPolicy].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DispatcherError<Id> {
+    /// A request was registered with the ID of another pending request.
+    DuplicateId(Id),
+
+    /// A response was received for an ID without a pending request.
+    UnknownId(Id),
+
+    /// The [`Dispatcher`][super::Dispatcher] has been closed.
+    Closed,
+
+    /// A response was received for a request whose receiver has been dropped.
+    ReceiverDropped(Id),
+}
+
+impl<Id> Display for DispatcherError<Id>
+where
+    Id: Debug,
+{
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DispatcherError::DuplicateId(id) => {
+                write!(formatter, "Request ID {:?} is already pending", id)
+            }
+            DispatcherError::UnknownId(id) => {
+                write!(
+                    formatter,
+                    "Received a response for unknown request ID {:?}",
+                    id
+                )
+            }
+            DispatcherError::Closed => write!(formatter, "Dispatcher has been closed"),
+            DispatcherError::ReceiverDropped(id) => write!(
+                formatter,
+                "Receiver for the response of request ID {:?} was dropped",
+                id
+            ),
+        }
+    }
+}
+
+impl<Id> Error for DispatcherError<Id> where Id: Debug {}
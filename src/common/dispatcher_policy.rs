@@ -0,0 +1,136 @@
+use {
+    super::DispatcherError,
+    std::sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Which [`DispatcherError`] conditions a [`Dispatcher`][super::Dispatcher] reports as errors.
+///
+/// Conditions that aren't reported as errors are counted and ignored, and the counts can be
+/// retrieved with [`Dispatcher::ignored_errors`][super::Dispatcher::ignored_errors]. By default,
+/// only [`DispatcherError::DuplicateId`] and [`DispatcherError::Closed`] are reported as errors.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DispatcherPolicy {
+    duplicate_id: bool,
+    unknown_id: bool,
+    closed: bool,
+    receiver_dropped: bool,
+}
+
+impl DispatcherPolicy {
+    /// Create the default policy, which only reports [`DispatcherError::DuplicateId`] and
+    /// [`DispatcherError::Closed`] as errors.
+    pub fn new() -> Self {
+        DispatcherPolicy {
+            duplicate_id: true,
+            unknown_id: false,
+            closed: true,
+            receiver_dropped: false,
+        }
+    }
+
+    /// Create a policy that reports all conditions as errors.
+    pub fn strict() -> Self {
+        DispatcherPolicy {
+            duplicate_id: true,
+            unknown_id: true,
+            closed: true,
+            receiver_dropped: true,
+        }
+    }
+
+    /// Change if [`DispatcherError::DuplicateId`] is reported as an error.
+    ///
+    /// If it is ignored, the new request replaces the pending request, whose receiver then fails as
+    /// if its sender had been dropped.
+    pub fn fail_on_duplicate_id(mut self, is_error: bool) -> Self {
+        self.duplicate_id = is_error;
+        self
+    }
+
+    /// Change if [`DispatcherError::UnknownId`] is reported as an error.
+    pub fn fail_on_unknown_id(mut self, is_error: bool) -> Self {
+        self.unknown_id = is_error;
+        self
+    }
+
+    /// Change if [`DispatcherError::Closed`] is reported as an error.
+    ///
+    /// If it is ignored, items sent to a closed [`Dispatcher`][super::Dispatcher] are discarded.
+    pub fn fail_on_closed(mut self, is_error: bool) -> Self {
+        self.closed = is_error;
+        self
+    }
+
+    /// Change if [`DispatcherError::ReceiverDropped`] is reported as an error.
+    pub fn fail_on_receiver_dropped(mut self, is_error: bool) -> Self {
+        self.receiver_dropped = is_error;
+        self
+    }
+
+    /// Check if the `error` should be reported.
+    pub fn is_error<Id>(&self, error: &DispatcherError<Id>) -> bool {
+        match error {
+            DispatcherError::DuplicateId(_) => self.duplicate_id,
+            DispatcherError::UnknownId(_) => self.unknown_id,
+            DispatcherError::Closed => self.closed,
+            DispatcherError::ReceiverDropped(_) => self.receiver_dropped,
+        }
+    }
+}
+
+impl Default for DispatcherPolicy {
+    fn default() -> Self {
+        DispatcherPolicy::new()
+    }
+}
+
+/// The number of times each [`DispatcherError`] condition was ignored by a
+/// [`Dispatcher`][super::Dispatcher] and all of its clones.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct IgnoredErrors {
+    /// The number of [`DispatcherError::DuplicateId`] conditions that were ignored.
+    pub duplicate_ids: u64,
+
+    /// The number of [`DispatcherError::UnknownId`] conditions that were ignored.
+    pub unknown_ids: u64,
+
+    /// The number of [`DispatcherError::Closed`] conditions that were ignored.
+    pub closed: u64,
+
+    /// The number of [`DispatcherError::ReceiverDropped`] conditions that were ignored.
+    pub receivers_dropped: u64,
+}
+
+/// The counters of ignored [`DispatcherError`] conditions, shared by all clones of a
+/// [`Dispatcher`][super::Dispatcher].
+#[derive(Debug, Default)]
+pub struct IgnoredErrorCounters {
+    duplicate_ids: AtomicU64,
+    unknown_ids: AtomicU64,
+    closed: AtomicU64,
+    receivers_dropped: AtomicU64,
+}
+
+impl IgnoredErrorCounters {
+    /// Count an ignored `error`.
+    pub fn count<Id>(&self, error: &DispatcherError<Id>) {
+        let counter = match error {
+            DispatcherError::DuplicateId(_) => &self.duplicate_ids,
+            DispatcherError::UnknownId(_) => &self.unknown_ids,
+            DispatcherError::Closed => &self.closed,
+            DispatcherError::ReceiverDropped(_) => &self.receivers_dropped,
+        };
+
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Retrieve the current counts.
+    pub fn snapshot(&self) -> IgnoredErrors {
+        IgnoredErrors {
+            duplicate_ids: self.duplicate_ids.load(Ordering::Relaxed),
+            unknown_ids: self.unknown_ids.load(Ordering::Relaxed),
+            closed: self.closed.load(Ordering::Relaxed),
+            receivers_dropped: self.receivers_dropped.load(Ordering::Relaxed),
+        }
+    }
+}
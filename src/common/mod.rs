@@ -1,3 +1,16 @@
+mod call_error;
 mod dispatcher;
+mod dispatcher_error;
+mod dispatcher_policy;
+mod pending_requests;
+mod pending_response;
+mod request_id;
 
-pub use self::dispatcher::Dispatcher;
+pub use self::{
+    call_error::CallError,
+    dispatcher::Dispatcher,
+    dispatcher_error::DispatcherError,
+    dispatcher_policy::{DispatcherPolicy, IgnoredErrors},
+    pending_response::PendingResponse,
+    request_id::RequestId,
+};
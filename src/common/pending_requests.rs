@@ -0,0 +1,206 @@
+use {
+    async_oneshot::Sender,
+    std::{
+        collections::{btree_map, hash_map, BTreeMap, HashMap},
+        hash::Hash,
+        task::Waker,
+        time::Instant,
+    },
+};
+
+/// The requests waiting for a response in a [`Dispatcher`][super::Dispatcher].
+///
+/// Requests with a deadline are also tracked in a queue ordered by their deadlines, so that only
+/// a single timer is needed for the earliest deadline.
+#[derive(Debug)]
+pub struct PendingRequests<Id, Response> {
+    requests: HashMap<Id, PendingRequest<Response>>,
+    deadlines: BTreeMap<Instant, Vec<Id>>,
+    timer_generation: u64,
+    timer_wakers: Vec<Waker>,
+    closed: bool,
+}
+
+/// The endpoint for the response of a pending request, and the request's deadline if it has one.
+#[derive(Debug)]
+struct PendingRequest<Response> {
+    sender: Sender<Response>,
+    deadline: Option<Instant>,
+}
+
+impl<Id, Response> PendingRequests<Id, Response> {
+    /// Create an empty and open set of pending requests.
+    pub fn new() -> Self {
+        PendingRequests {
+            requests: HashMap::new(),
+            deadlines: BTreeMap::new(),
+            timer_generation: 0,
+            timer_wakers: Vec::new(),
+            closed: false,
+        }
+    }
+
+    /// Check if the pending requests have been closed.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Retrieve the earliest deadline of the pending requests, if there is one.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.deadlines.keys().next().copied()
+    }
+
+    /// Retrieve the current timer generation, which changes whenever the timers must be
+    /// rescheduled.
+    pub fn timer_generation(&self) -> u64 {
+        self.timer_generation
+    }
+
+    /// Register the `waker` of a timer task to wake up when the timers must be rescheduled.
+    ///
+    /// Each task is only registered once, so that all timer tasks share the same notification
+    /// without replacing each other's.
+    pub fn register_timer(&mut self, waker: &Waker) {
+        if !self
+            .timer_wakers
+            .iter()
+            .any(|registered| registered.will_wake(waker))
+        {
+            self.timer_wakers.push(waker.clone());
+        }
+    }
+
+    /// Wake up all timer tasks so that they reschedule their timers.
+    fn reschedule_timers(&mut self) {
+        self.timer_generation = self.timer_generation.wrapping_add(1);
+
+        for waker in self.timer_wakers.drain(..) {
+            waker.wake();
+        }
+    }
+
+    /// Mark the pending requests as closed, and remove the endpoints of all requests.
+    ///
+    /// The timer tasks are also woken up, so that they stop waiting for deadlines.
+    pub fn close(&mut self) -> Vec<Sender<Response>> {
+        self.closed = true;
+        self.reschedule_timers();
+        self.deadlines.clear();
+        self.requests
+            .drain()
+            .map(|(_, request)| request.sender)
+            .collect()
+    }
+}
+
+impl<Id, Response> PendingRequests<Id, Response>
+where
+    Id: Eq + Hash,
+{
+    /// Add a request without a deadline.
+    ///
+    /// The request is ignored if its receiver has already been dropped.
+    pub fn insert(&mut self, id: Id, sender: Sender<Response>) {
+        if sender.is_closed() {
+            return;
+        }
+
+        let request = PendingRequest {
+            sender,
+            deadline: None,
+        };
+
+        self.requests.insert(id, request);
+    }
+
+    /// Check if there is a pending request with the `id`.
+    pub fn contains(&self, id: &Id) -> bool {
+        self.requests.contains_key(id)
+    }
+
+    /// Remove a request and return the endpoint for its response.
+    pub fn remove(&mut self, id: &Id) -> Option<Sender<Response>> {
+        let request = self.requests.remove(id)?;
+
+        if let Some(deadline) = request.deadline {
+            self.forget_deadline(id, deadline);
+        }
+
+        Some(request.sender)
+    }
+
+    /// Remove the requests whose deadline is not after `now`, and return their endpoints.
+    ///
+    /// Queued IDs whose request was replaced by one with a different deadline are skipped.
+    pub fn expire(&mut self, now: Instant) -> Vec<Sender<Response>> {
+        let mut expired = Vec::new();
+
+        while let Some(entry) = self.deadlines.first_entry() {
+            if *entry.key() > now {
+                break;
+            }
+
+            let (deadline, ids) = entry.remove_entry();
+
+            for id in ids {
+                if let hash_map::Entry::Occupied(request) = self.requests.entry(id) {
+                    if request.get().deadline == Some(deadline) {
+                        expired.push(request.remove().sender);
+                    }
+                }
+            }
+        }
+
+        expired
+    }
+
+    /// Remove an `id` from the queue entry of its `deadline`.
+    fn forget_deadline(&mut self, id: &Id, deadline: Instant) {
+        if let btree_map::Entry::Occupied(mut entry) = self.deadlines.entry(deadline) {
+            entry.get_mut().retain(|queued_id| queued_id != id);
+
+            if entry.get().is_empty() {
+                entry.remove();
+            }
+        }
+    }
+}
+
+impl<Id, Response> PendingRequests<Id, Response>
+where
+    Id: Clone + Eq + Hash,
+{
+    /// Add a request that expires at the `deadline`.
+    ///
+    /// If the `deadline` is earlier than all other deadlines, the timer is notified so that it
+    /// can be rescheduled.
+    ///
+    /// The request is ignored if its receiver has already been dropped.
+    pub fn insert_with_deadline(&mut self, id: Id, sender: Sender<Response>, deadline: Instant) {
+        if sender.is_closed() {
+            return;
+        }
+
+        let is_earliest = match self.next_deadline() {
+            Some(next_deadline) => deadline < next_deadline,
+            None => true,
+        };
+
+        if is_earliest {
+            self.reschedule_timers();
+        }
+
+        let request = PendingRequest {
+            sender,
+            deadline: Some(deadline),
+        };
+
+        if let Some(replaced) = self.requests.insert(id.clone(), request) {
+            if let Some(replaced_deadline) = replaced.deadline {
+                self.forget_deadline(&id, replaced_deadline);
+            }
+        }
+
+        self.deadlines.entry(deadline).or_default().push(id);
+    }
+}
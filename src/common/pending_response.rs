@@ -0,0 +1,117 @@
+use {
+    super::pending_requests::PendingRequests,
+    async_oneshot::{Closed, Receiver},
+    futures::FutureExt,
+    std::{
+        fmt::{self, Debug, Formatter},
+        future::Future,
+        hash::Hash,
+        pin::Pin,
+        sync::{Arc, Mutex, PoisonError, Weak},
+        task::{Context, Poll},
+    },
+};
+
+/// A hook called with the ID of each request that is cancelled because its [`PendingResponse`]
+/// was dropped.
+pub type CancelHook<Id> = dyn Fn(Id) + Send + Sync;
+
+/// The receiving endpoint for the `Response` of a request registered in a
+/// [`Dispatcher`][super::Dispatcher], which cancels the request if it is dropped.
+///
+/// Created by [`Dispatcher::cancellable_oneshot`][super::Dispatcher::cancellable_oneshot]. If
+/// the [`PendingResponse`] is dropped before the `Response` is received, the request is
+/// immediately removed from the [`Dispatcher`][super::Dispatcher] and its cancel hook is called
+/// with the request's ID.
+pub struct PendingResponse<Id, Response>
+where
+    Id: Eq + Hash,
+{
+    receiver: Receiver<Response>,
+    id: Option<Id>,
+    pending_requests: Weak<Mutex<PendingRequests<Id, Response>>>,
+    cancel_hook: Option<Arc<CancelHook<Id>>>,
+}
+
+impl<Id, Response> PendingResponse<Id, Response>
+where
+    Id: Eq + Hash,
+{
+    /// Create a new [`PendingResponse`] for the request with the `id`, that removes the request
+    /// from the `pending_requests` and calls the `cancel_hook` if it is dropped early.
+    pub(crate) fn new(
+        id: Id,
+        receiver: Receiver<Response>,
+        pending_requests: Weak<Mutex<PendingRequests<Id, Response>>>,
+        cancel_hook: Option<Arc<CancelHook<Id>>>,
+    ) -> Self {
+        PendingResponse {
+            receiver,
+            id: Some(id),
+            pending_requests,
+            cancel_hook,
+        }
+    }
+}
+
+impl<Id, Response> Debug for PendingResponse<Id, Response>
+where
+    Id: Debug + Eq + Hash,
+    Response: Debug,
+{
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("PendingResponse")
+            .field("receiver", &self.receiver)
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The [`PendingResponse`] never pins the request ID.
+impl<Id, Response> Unpin for PendingResponse<Id, Response> where Id: Eq + Hash {}
+
+impl<Id, Response> Future for PendingResponse<Id, Response>
+where
+    Id: Eq + Hash,
+{
+    type Output = Result<Response, Closed>;
+
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        let result = self.receiver.poll_unpin(context);
+
+        if result.is_ready() {
+            self.id = None;
+        }
+
+        result
+    }
+}
+
+/// Cancel the request if its `Response` hasn't been received.
+///
+/// The cancel hook is called after the pending requests lock is released, so it can use the
+/// [`Dispatcher`][super::Dispatcher].
+impl<Id, Response> Drop for PendingResponse<Id, Response>
+where
+    Id: Eq + Hash,
+{
+    fn drop(&mut self) {
+        let (id, pending_requests) = match (self.id.take(), self.pending_requests.upgrade()) {
+            (Some(id), Some(pending_requests)) => (id, pending_requests),
+            _ => return,
+        };
+
+        let was_pending = pending_requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&id)
+            .is_some();
+
+        if was_pending {
+            if let Some(hook) = &self.cancel_hook {
+                hook(id);
+            }
+        }
+    }
+}
@@ -0,0 +1,2 @@
+/// The identifier of a request, used to match it with its response.
+pub type RequestId = u64;
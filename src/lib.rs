@@ -1,4 +1,40 @@
+#[cfg(not(any(feature = "tokio", feature = "async-lock")))]
+compile_error!(
+    "ezrpc requires a lock for the generated services, so either the `tokio` or the `async-lock` \
+    feature must be enabled"
+);
+
+pub mod client;
+#[cfg(feature = "serde")]
+pub mod codec;
 mod common;
-mod util;
+pub mod server;
+
+pub use {
+    self::common::{
+        CallError, Dispatcher, DispatcherError, DispatcherPolicy, IgnoredErrors, PendingResponse,
+        RequestId,
+    },
+    ezrpc_proc_macros::tower,
+};
+
+/// Dependencies used by the generated code.
+///
+/// This is not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use {futures, tower};
+
+    #[cfg(feature = "serde")]
+    pub use serde;
+
+    /// The lock used by services with `&mut self` methods.
+    ///
+    /// The lock from `tokio` is preferred if both the `tokio` and `async-lock` features are
+    /// enabled.
+    #[cfg(feature = "tokio")]
+    pub use tokio::sync::RwLock;
 
-pub use {self::common::Dispatcher, ezrpc_proc_macros::tower};
+    #[cfg(all(feature = "async-lock", not(feature = "tokio")))]
+    pub use async_lock::RwLock;
+}
@@ -0,0 +1,40 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+/// An error that stopped a [`Server`][super::Server].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ServeError<TransportError, ServiceError> {
+    /// The transport failed.
+    Transport(TransportError),
+
+    /// The service failed to become ready.
+    Service(ServiceError),
+}
+
+impl<TransportError, ServiceError> Display for ServeError<TransportError, ServiceError>
+where
+    TransportError: Display,
+    ServiceError: Display,
+{
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ServeError::Transport(error) => write!(formatter, "Transport failed: {}", error),
+            ServeError::Service(error) => write!(formatter, "Service failed: {}", error),
+        }
+    }
+}
+
+impl<TransportError, ServiceError> Error for ServeError<TransportError, ServiceError>
+where
+    TransportError: Error + 'static,
+    ServiceError: Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ServeError::Transport(error) => Some(error),
+            ServeError::Service(error) => Some(error),
+        }
+    }
+}
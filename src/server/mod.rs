@@ -0,0 +1,9 @@
+//! Serving a [`tower::Service`] to remote clients.
+
+mod error;
+mod serve;
+
+pub use self::{
+    error::ServeError,
+    serve::{serve, Server},
+};
@@ -0,0 +1,173 @@
+use {
+    super::ServeError,
+    crate::RequestId,
+    futures::{
+        future, pin_mut, ready, stream::FuturesUnordered, FutureExt, Sink, Stream, StreamExt,
+    },
+    std::{collections::VecDeque, task::Poll},
+};
+
+/// A server that handles the requests received through a transport with a [`tower::Service`].
+///
+/// Requests are received as `(RequestId, Request)` envelopes, and the responses are sent as
+/// `(RequestId, Result<Response, Error>)` envelopes as soon as each call finishes, which may be
+/// in a different order than the requests were received. Up to the concurrency limit of calls
+/// are handled at the same time, and no more requests are read from the transport while the
+/// limit is reached.
+pub struct Server<S> {
+    service: S,
+    concurrency_limit: usize,
+}
+
+impl<S> Server<S> {
+    /// The default maximum number of concurrent calls.
+    pub const DEFAULT_CONCURRENCY_LIMIT: usize = 64;
+
+    /// Create a new [`Server`] that handles requests with the `service`.
+    pub fn new(service: S) -> Self {
+        Server {
+            service,
+            concurrency_limit: Self::DEFAULT_CONCURRENCY_LIMIT,
+        }
+    }
+
+    /// Change the maximum number of concurrent calls.
+    ///
+    /// Responses that haven't been sent yet also count towards the limit.
+    ///
+    /// # Panics
+    ///
+    /// If the `concurrency_limit` is zero.
+    pub fn with_concurrency_limit(mut self, concurrency_limit: usize) -> Self {
+        assert!(concurrency_limit > 0, "Concurrency limit must not be zero");
+
+        self.concurrency_limit = concurrency_limit;
+        self
+    }
+
+    /// Handle the requests received through the `transport` until the peer closes it.
+    ///
+    /// Once the peer stops sending requests, the remaining calls are finished, their responses
+    /// are sent and the `transport` is closed. If the `service` fails to become ready, no more
+    /// requests are read and the connection is closed in the same way, and then the service's
+    /// error is returned.
+    ///
+    /// Requests that the `transport` fails to receive, like requests that can't be decoded, are
+    /// skipped without affecting the other calls. If the `transport` ends right after such a
+    /// failure, the failure is returned once the remaining calls finish. Returns an error
+    /// immediately if the `transport` fails to send a response.
+    pub async fn serve<Request, Transport, TransportError>(
+        self,
+        transport: Transport,
+    ) -> Result<(), ServeError<TransportError, S::Error>>
+    where
+        S: tower::Service<Request>,
+        Transport: Stream<Item = Result<(RequestId, Request), TransportError>>
+            + Sink<(RequestId, Result<S::Response, S::Error>), Error = TransportError>,
+    {
+        let Server {
+            mut service,
+            concurrency_limit,
+        } = self;
+
+        pin_mut!(transport);
+
+        let mut calls = FuturesUnordered::new();
+        let mut responses = VecDeque::new();
+        let mut accepting = true;
+        let mut unflushed = false;
+        let mut service_error = None;
+        let mut receive_error = None;
+
+        future::poll_fn(|context| loop {
+            while let Poll::Ready(Some(response)) = calls.poll_next_unpin(context) {
+                responses.push_back(response);
+            }
+
+            while !responses.is_empty() {
+                if transport
+                    .as_mut()
+                    .poll_ready(context)
+                    .map_err(ServeError::Transport)?
+                    .is_pending()
+                {
+                    break;
+                }
+
+                let response = responses
+                    .pop_front()
+                    .expect("There is at least one response to send");
+
+                transport
+                    .as_mut()
+                    .start_send(response)
+                    .map_err(ServeError::Transport)?;
+                unflushed = true;
+            }
+
+            if accepting && calls.len() + responses.len() < concurrency_limit {
+                match service.poll_ready(context) {
+                    Poll::Ready(Ok(())) => match transport.as_mut().poll_next(context) {
+                        Poll::Ready(Some(Ok((id, request)))) => {
+                            receive_error = None;
+                            calls.push(service.call(request).map(move |result| (id, result)));
+                            continue;
+                        }
+                        Poll::Ready(Some(Err(error))) => {
+                            // Yield before receiving the next request, so that a transport that
+                            // keeps failing doesn't block the executor.
+                            receive_error = Some(error);
+                            context.waker().wake_by_ref();
+                        }
+                        Poll::Ready(None) => accepting = false,
+                        Poll::Pending => {}
+                    },
+                    Poll::Ready(Err(error)) => {
+                        accepting = false;
+                        service_error = Some(error);
+                    }
+                    Poll::Pending => {}
+                }
+            }
+
+            if unflushed
+                && transport
+                    .as_mut()
+                    .poll_flush(context)
+                    .map_err(ServeError::Transport)?
+                    .is_ready()
+            {
+                unflushed = false;
+            }
+
+            if !accepting && calls.is_empty() && responses.is_empty() && !unflushed {
+                ready!(transport.as_mut().poll_close(context)).map_err(ServeError::Transport)?;
+
+                return Poll::Ready(match (service_error.take(), receive_error.take()) {
+                    (Some(error), _) => Err(ServeError::Service(error)),
+                    (None, Some(error)) => Err(ServeError::Transport(error)),
+                    (None, None) => Ok(()),
+                });
+            }
+
+            return Poll::Pending;
+        })
+        .await
+    }
+}
+
+/// Handle the requests received through the `transport` with the `service` until the peer closes
+/// it.
+///
+/// This uses a [`Server`] with the default concurrency limit.
+pub async fn serve<S, Request, Transport, TransportError>(
+    service: S,
+    transport: Transport,
+) -> Result<(), ServeError<TransportError, S::Error>>
+where
+    S: tower::Service<Request>,
+    Transport: Stream<Item = Result<(RequestId, Request), TransportError>>
+        + Sink<(RequestId, Result<S::Response, S::Error>), Error = TransportError>,
+{
+    Server::new(service).serve(transport).await
+}
@@ -1,3 +0,0 @@
-mod mutex_state_machine;
-
-pub use self::mutex_state_machine::MutexStateMachine;
@@ -0,0 +1,68 @@
+use {
+    ezrpc::CallError,
+    futures::{executor, task::noop_waker_ref, FutureExt},
+    std::task::{Context, Poll},
+    tower::Service as _,
+};
+
+pub struct Counter {
+    count: u32,
+}
+
+#[ezrpc::tower(concurrency = actor)]
+impl Counter {
+    pub fn increment(&mut self) -> u32 {
+        self.count += 1;
+        self.count
+    }
+}
+
+#[test]
+fn calls_are_handled_by_the_actor() {
+    let (mut service, actor) = Service::actor(Counter { count: 0 }, 1);
+
+    executor::block_on(async move {
+        let calls = async move {
+            assert_eq!(service.increment().await, Ok(1));
+            assert_eq!(service.increment().await, Ok(2));
+        };
+
+        futures::join!(actor, calls);
+    });
+}
+
+#[test]
+fn dropped_actor_makes_the_service_unavailable() {
+    let (mut service, actor) = Service::actor(Counter { count: 0 }, 1);
+
+    drop(actor);
+
+    let mut context = Context::from_waker(noop_waker_ref());
+
+    assert!(matches!(
+        service.poll_ready(&mut context),
+        Poll::Ready(Err(CallError::Unavailable))
+    ));
+    assert_eq!(
+        service.call(Request::Increment).now_or_never(),
+        Some(Err(CallError::Unavailable))
+    );
+    assert_eq!(
+        executor::block_on(service.increment()),
+        Err(CallError::Unavailable)
+    );
+}
+
+#[test]
+fn actor_dropped_with_queued_requests_fails_them() {
+    let (mut service, actor) = Service::actor(Counter { count: 0 }, 1);
+    let mut context = Context::from_waker(noop_waker_ref());
+
+    assert!(service.poll_ready(&mut context).is_ready());
+
+    let response = service.call(Request::Increment);
+
+    drop(actor);
+
+    assert_eq!(executor::block_on(response), Err(CallError::Unavailable));
+}
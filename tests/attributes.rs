@@ -0,0 +1,63 @@
+use futures::executor;
+
+pub struct Calculator;
+
+#[ezrpc::tower(derive(Debug, Clone, PartialEq), attr(derive(Eq)))]
+impl Calculator {
+    /// Double a number.
+    pub fn double(&self, number: u32) -> u32 {
+        number * 2
+    }
+
+    pub fn negate(&self, flag: bool) -> bool {
+        !flag
+    }
+
+    /// Uses a type that doesn't exist, so any generated item that is kept will fail to compile.
+    #[cfg(any())]
+    pub fn disabled(&self, value: Missing) -> Missing {
+        value
+    }
+}
+
+fn assert_eq_is_implemented<T: Eq>(_: &T) {}
+
+#[test]
+fn derives_are_placed_on_the_enums() {
+    let request = Request::Double { number: 2 };
+    let response = Response::Double(4);
+
+    assert_eq!(request.clone(), request);
+    assert_eq!(format!("{:?}", request), "Double { number: 2 }");
+    assert_eq!(response.clone(), response);
+    assert_eq!(format!("{:?}", response), "Double(4)");
+
+    assert_eq_is_implemented(&request);
+    assert_eq_is_implemented(&response);
+}
+
+#[test]
+fn disabled_methods_are_left_out() {
+    let request = Request::Negate { flag: true };
+    let response = Response::Negate(false);
+
+    // The matches are only exhaustive if the disabled method has no variants.
+    let request_method = match request {
+        Request::Double { .. } => "double",
+        Request::Negate { .. } => "negate",
+    };
+    let response_method = match response {
+        Response::Double(_) => "double",
+        Response::Negate(_) => "negate",
+    };
+
+    assert_eq!(request_method, "negate");
+    assert_eq!(response_method, "negate");
+
+    let mut service = Service::new(Calculator);
+
+    executor::block_on(async move {
+        assert_eq!(service.double(2).await, Ok(4));
+        assert_eq!(service.negate(true).await, Ok(false));
+    });
+}
@@ -0,0 +1,75 @@
+use {
+    futures::executor,
+    std::path::{Path, PathBuf},
+    tower::{Service as _, ServiceExt as _},
+};
+
+pub struct Library {
+    name: String,
+}
+
+#[ezrpc::tower(derive(Debug, PartialEq))]
+impl Library {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn shout(&self, text: &str) -> String {
+        text.to_uppercase()
+    }
+
+    pub fn checksum(&self, bytes: &[u8]) -> String {
+        bytes
+            .iter()
+            .map(|&byte| byte as u32)
+            .sum::<u32>()
+            .to_string()
+    }
+
+    pub fn extension(&self, path: &Path) -> String {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_owned()
+    }
+}
+
+#[test]
+fn borrowed_parameters_are_sent_as_owned_values() {
+    let mut service = Service::new(Library {
+        name: "books".to_owned(),
+    });
+
+    executor::block_on(async move {
+        let response = service
+            .ready()
+            .await
+            .unwrap()
+            .call(Request::Extension {
+                path: PathBuf::from("book.txt"),
+            })
+            .await;
+
+        assert_eq!(response, Ok(Response::Extension("txt".to_owned())));
+        assert_eq!(service.shout("hi").await, Ok("HI".to_owned()));
+        assert_eq!(service.checksum(&[1, 2, 3]).await, Ok("6".to_owned()));
+        assert_eq!(
+            service.extension(Path::new("a.rs")).await,
+            Ok("rs".to_owned())
+        );
+    });
+}
+
+#[test]
+fn borrowed_results_are_cloned() {
+    let mut service = Service::new(Library {
+        name: "books".to_owned(),
+    });
+
+    executor::block_on(async move {
+        let response = service.ready().await.unwrap().call(Request::Name).await;
+
+        assert_eq!(response, Ok(Response::Name("books".to_owned())));
+        assert_eq!(service.name().await, Ok("books".to_owned()));
+    });
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use {
+    self::common::ChannelTransport,
+    ezrpc::{client::Connection, CallError, RequestId},
+    futures::{
+        channel::mpsc,
+        executor::{self, LocalPool},
+        stream,
+        task::LocalSpawnExt,
+        FutureExt, StreamExt,
+    },
+    tower::{Service as _, ServiceExt},
+};
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum GreeterError {
+    InvalidNumber(String),
+    Unavailable,
+}
+
+impl From<CallError<GreeterError>> for GreeterError {
+    fn from(error: CallError<GreeterError>) -> Self {
+        match error {
+            CallError::Failed(error) => error,
+            CallError::Unavailable | CallError::UnexpectedResponse => GreeterError::Unavailable,
+        }
+    }
+}
+
+#[ezrpc::tower(remote_client)]
+pub trait Greeter {
+    async fn greet(&self, name: String) -> Result<String, GreeterError>;
+    async fn parse(&self, text: String) -> Result<u8, GreeterError>;
+}
+
+pub struct Local;
+
+impl Greeter for Local {
+    async fn greet(&self, name: String) -> Result<String, GreeterError> {
+        Ok(format!("Hello, {}!", name))
+    }
+
+    async fn parse(&self, text: String) -> Result<u8, GreeterError> {
+        text.parse().map_err(|_| GreeterError::InvalidNumber(text))
+    }
+}
+
+#[test]
+fn client_wraps_a_remote_client() {
+    let (transport, request_sender, response_receiver) = ChannelTransport::new();
+    let (remote_client, task) =
+        RemoteClient::new(request_sender, response_receiver.map(Ok::<_, ()>));
+    let client = Client::new(remote_client);
+
+    executor::block_on(async move {
+        let calls = async move {
+            assert_eq!(
+                client.greet("world".to_owned()).await,
+                Ok("Hello, world!".to_owned())
+            );
+            assert_eq!(client.parse("42".to_owned()).await, Ok(42));
+            assert_eq!(
+                client.parse("x".to_owned()).await,
+                Err(GreeterError::InvalidNumber("x".to_owned()))
+            );
+        };
+        let server = async move {
+            ezrpc::server::serve(Service::new(Local), transport)
+                .await
+                .unwrap();
+        };
+
+        futures::join!(calls, server, task);
+    });
+}
+
+#[test]
+fn client_reports_a_closed_connection_through_the_method_error() {
+    let (requests, _) = mpsc::unbounded();
+    let responses = stream::empty::<Result<(RequestId, Result<Response, GreeterError>), ()>>();
+    let (remote_client, task) = RemoteClient::new(requests, responses);
+    let client = Client::new(remote_client);
+
+    executor::block_on(async move {
+        let call = client.greet("world".to_owned());
+        let (result, ()) = futures::join!(call, task);
+
+        assert_eq!(result, Err(GreeterError::Unavailable));
+    });
+}
+
+#[test]
+fn request_that_fails_to_be_sent_is_unavailable() {
+    let (requests, closed_receiver) = mpsc::unbounded::<(RequestId, u8)>();
+    let responses = stream::pending::<Result<(RequestId, Result<u8, ()>), ()>>();
+    let (mut connection, task) = Connection::new(requests, responses);
+    let mut pool = LocalPool::new();
+
+    drop(closed_receiver);
+    pool.spawner().spawn_local(task).unwrap();
+
+    let response = executor::block_on(connection.ready()).unwrap().call(1);
+
+    pool.run_until_stalled();
+
+    assert_eq!(response.now_or_never(), Some(Err(CallError::Unavailable)));
+}
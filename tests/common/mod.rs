@@ -0,0 +1,68 @@
+use {
+    futures::{
+        channel::mpsc::{self, SendError, UnboundedReceiver, UnboundedSender},
+        Sink, Stream, StreamExt,
+    },
+    std::{
+        pin::Pin,
+        task::{Context, Poll},
+    },
+};
+
+/// An in-memory transport that receives `Incoming` items and sends `Outgoing` items through
+/// channels.
+pub struct ChannelTransport<Incoming, Outgoing> {
+    incoming: UnboundedReceiver<Incoming>,
+    outgoing: UnboundedSender<Outgoing>,
+}
+
+impl<Incoming, Outgoing> ChannelTransport<Incoming, Outgoing> {
+    /// Create a new [`ChannelTransport`], together with the sender for its `Incoming` items and
+    /// the receiver of its `Outgoing` items.
+    pub fn new() -> (Self, UnboundedSender<Incoming>, UnboundedReceiver<Outgoing>) {
+        let (incoming_sender, incoming) = mpsc::unbounded();
+        let (outgoing, outgoing_receiver) = mpsc::unbounded();
+        let transport = ChannelTransport { incoming, outgoing };
+
+        (transport, incoming_sender, outgoing_receiver)
+    }
+}
+
+impl<Incoming, Outgoing> Stream for ChannelTransport<Incoming, Outgoing> {
+    type Item = Result<Incoming, SendError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.incoming
+            .poll_next_unpin(context)
+            .map(|item| item.map(Ok))
+    }
+}
+
+impl<Incoming, Outgoing> Sink<Outgoing> for ChannelTransport<Incoming, Outgoing> {
+    type Error = SendError;
+
+    fn poll_ready(
+        mut self: Pin<&mut Self>,
+        context: &mut Context<'_>,
+    ) -> Poll<Result<(), SendError>> {
+        Pin::new(&mut self.outgoing).poll_ready(context)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Outgoing) -> Result<(), SendError> {
+        Pin::new(&mut self.outgoing).start_send(item)
+    }
+
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        context: &mut Context<'_>,
+    ) -> Poll<Result<(), SendError>> {
+        Pin::new(&mut self.outgoing).poll_flush(context)
+    }
+
+    fn poll_close(
+        mut self: Pin<&mut Self>,
+        context: &mut Context<'_>,
+    ) -> Poll<Result<(), SendError>> {
+        Pin::new(&mut self.outgoing).poll_close(context)
+    }
+}
@@ -0,0 +1,102 @@
+use {
+    futures::executor,
+    std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
+
+mod counter {
+    #[derive(Default)]
+    pub struct Counter {
+        pub count: u32,
+    }
+
+    #[ezrpc::tower]
+    impl Counter {
+        pub fn increment(&mut self) -> u32 {
+            self.count += 1;
+            self.count
+        }
+    }
+}
+
+mod shared_counter {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Doesn't implement `Default`, so the `Service` must not require it.
+    pub struct SharedCounter {
+        pub count: AtomicU32,
+    }
+
+    #[ezrpc::tower]
+    impl SharedCounter {
+        pub fn increment(&self) -> u32 {
+            self.count.fetch_add(1, Ordering::SeqCst) + 1
+        }
+    }
+}
+
+mod stateless {
+    pub struct Stateless;
+
+    #[ezrpc::tower]
+    impl Stateless {
+        pub fn answer() -> u32 {
+            42
+        }
+    }
+}
+
+#[test]
+fn inner_instance_sees_changes_from_mutable_methods() {
+    let mut service = counter::Service::new(counter::Counter { count: 10 });
+
+    executor::block_on(async move {
+        assert_eq!(service.increment().await, 11);
+        assert_eq!(service.inner().read().await.count, 11);
+    });
+}
+
+#[test]
+fn services_from_the_same_shared_instance_share_its_state() {
+    let mut service = counter::Service::default();
+    let mut shared = counter::Service::from_shared(service.inner().clone());
+    let mut clone = service.clone();
+
+    executor::block_on(async move {
+        assert_eq!(service.increment().await, 1);
+        assert_eq!(shared.increment().await, 2);
+        assert_eq!(clone.increment().await, 3);
+        assert_eq!(service.inner().read().await.count, 3);
+    });
+}
+
+#[test]
+fn services_share_the_instance_without_a_lock() {
+    let instance = Arc::new(shared_counter::SharedCounter {
+        count: AtomicU32::new(0),
+    });
+    let mut service = shared_counter::Service::from_shared(instance.clone());
+    let mut clone = service.clone();
+
+    assert!(Arc::ptr_eq(service.inner(), &instance));
+
+    executor::block_on(async move {
+        assert_eq!(service.increment().await, 1);
+        assert_eq!(clone.increment().await, 2);
+    });
+
+    assert_eq!(instance.count.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn services_without_an_instance_are_created_without_arguments() {
+    let mut service = stateless::Service::new();
+    let mut default: stateless::Service = Default::default();
+
+    executor::block_on(async move {
+        assert_eq!(service.answer().await, 42);
+        assert_eq!(default.answer().await, 42);
+    });
+}
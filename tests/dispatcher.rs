@@ -0,0 +1,390 @@
+use {
+    async_oneshot::Sender,
+    ezrpc::{Dispatcher, DispatcherError, DispatcherPolicy, IgnoredErrors},
+    futures::{
+        executor::{self, LocalPool},
+        future,
+        task::{noop_waker_ref, LocalSpawnExt},
+        FutureExt, Sink, SinkExt,
+    },
+    futures_timer::Delay,
+    std::{
+        cell::Cell,
+        pin::Pin,
+        rc::Rc,
+        sync::{Arc, Mutex},
+        task::{Context, Poll},
+        time::{Duration, Instant},
+    },
+};
+
+/// A clone that is ready to send but never sends an item must not block the other clones.
+#[test]
+fn ready_clone_does_not_block_other_clones() {
+    let mut idle: Dispatcher<u64, u8> = Dispatcher::new();
+    let mut registrar = idle.clone();
+    let mut responder = idle.clone();
+    let mut context = Context::from_waker(noop_waker_ref());
+
+    let readiness = Sink::<(u64, u8)>::poll_ready(Pin::new(&mut idle), &mut context);
+
+    assert!(matches!(readiness, Poll::Ready(Ok(()))));
+
+    let (sender, receiver) = async_oneshot::oneshot();
+
+    registrar
+        .send((1, sender))
+        .now_or_never()
+        .expect("Registration was blocked by a ready clone")
+        .expect("Registration failed");
+
+    responder
+        .send((1, 10))
+        .now_or_never()
+        .expect("Response was blocked by a ready clone")
+        .expect("Response dispatch failed");
+
+    assert!(matches!(receiver.now_or_never(), Some(Ok(10))));
+}
+
+/// Requests that don't receive a response before their deadline are resolved with the timeout
+/// response, while other requests are unaffected.
+#[test]
+fn expired_requests_receive_the_timeout_response() {
+    let mut dispatcher: Dispatcher<u64, u8> = Dispatcher::new();
+    let expiry = dispatcher.expire_requests(0);
+    let now = Instant::now();
+
+    executor::block_on(future::join(expiry, async {
+        let (expiring, expiring_receiver) = async_oneshot::oneshot();
+        let (answered, answered_receiver) = async_oneshot::oneshot();
+        let (waiting, waiting_receiver) = async_oneshot::oneshot();
+
+        dispatcher
+            .send((1, expiring, now + Duration::from_millis(20)))
+            .await
+            .unwrap();
+        dispatcher
+            .send((2, answered, now + Duration::from_millis(20)))
+            .await
+            .unwrap();
+        dispatcher.send((3, waiting)).await.unwrap();
+        dispatcher.send((2, 2)).await.unwrap();
+
+        assert!(matches!(answered_receiver.await, Ok(2)));
+        assert!(matches!(expiring_receiver.await, Ok(0)));
+
+        dispatcher.send((3, 3)).await.unwrap();
+
+        assert!(matches!(waiting_receiver.await, Ok(3)));
+
+        SinkExt::<(u64, u8)>::close(&mut dispatcher).await.unwrap();
+    }));
+}
+
+/// Adding a deadline earlier than the one the timer is waiting for reschedules the timer.
+#[test]
+fn earlier_deadline_reschedules_the_timer() {
+    let mut dispatcher: Dispatcher<u64, u8> = Dispatcher::new();
+    let expiry = dispatcher.expire_requests(0);
+    let start = Instant::now();
+
+    executor::block_on(future::join(expiry, async {
+        let (late, _late_receiver) = async_oneshot::oneshot();
+        let (early, early_receiver) = async_oneshot::oneshot();
+
+        dispatcher
+            .send((1, late, start + Duration::from_secs(60)))
+            .await
+            .unwrap();
+
+        // Let the timer start waiting for the late deadline.
+        Delay::new(Duration::from_millis(10)).await;
+
+        dispatcher
+            .send((2, early, Instant::now() + Duration::from_millis(10)))
+            .await
+            .unwrap();
+
+        assert!(matches!(early_receiver.await, Ok(0)));
+        assert!(start.elapsed() < Duration::from_secs(30));
+
+        SinkExt::<(u64, u8)>::close(&mut dispatcher).await.unwrap();
+    }));
+}
+
+/// Multiple expiry tasks on an idle dispatcher don't keep waking each other up.
+#[test]
+fn expiry_tasks_sleep_while_idle() {
+    let dispatcher: Dispatcher<u64, u8> = Dispatcher::new();
+    let polls = Rc::new(Cell::new(0));
+    let mut pool = LocalPool::new();
+
+    for _ in 0..2 {
+        let mut expiry = Box::pin(dispatcher.expire_requests(0));
+        let polls = polls.clone();
+
+        pool.spawner()
+            .spawn_local(future::poll_fn(move |context| {
+                polls.set(polls.get() + 1);
+                expiry.poll_unpin(context)
+            }))
+            .unwrap();
+    }
+
+    pool.run_until(Delay::new(Duration::from_millis(50)));
+
+    assert!(
+        polls.get() <= 4,
+        "Expiry tasks were polled {} times",
+        polls.get()
+    );
+
+    let (sender, receiver) = async_oneshot::oneshot();
+    let mut registrar = dispatcher.clone();
+
+    pool.run_until(async {
+        registrar
+            .send((1, sender, Instant::now() + Duration::from_millis(10)))
+            .await
+            .unwrap();
+
+        assert!(matches!(receiver.await, Ok(0)));
+
+        SinkExt::<(u64, u8)>::close(&mut registrar).await.unwrap();
+    });
+
+    pool.run();
+}
+
+/// Dropping a pending response removes its request and calls the cancel hook, even if the
+/// dispatcher isn't used afterwards.
+#[test]
+fn dropped_response_cancels_on_idle_dispatcher() {
+    let cancelled = Arc::new(Mutex::new(Vec::new()));
+    let hook_cancelled = cancelled.clone();
+    let mut dispatcher: Dispatcher<u64, u8> =
+        Dispatcher::with_cancel_hook(move |id| hook_cancelled.lock().unwrap().push(id));
+
+    let (dropped_sender, dropped) = dispatcher.cancellable_oneshot(1);
+    let (kept_sender, kept) = dispatcher.cancellable_oneshot(2);
+
+    executor::block_on(async {
+        dispatcher.send((1, dropped_sender)).await.unwrap();
+        dispatcher.send((2, kept_sender)).await.unwrap();
+    });
+
+    drop(dropped);
+
+    assert_eq!(*cancelled.lock().unwrap(), vec![1]);
+
+    executor::block_on(async {
+        dispatcher.send((1, 1)).await.unwrap();
+        dispatcher.send((2, 2)).await.unwrap();
+    });
+
+    assert_eq!(dispatcher.ignored_errors().unknown_ids, 1);
+    assert!(matches!(kept.now_or_never(), Some(Ok(2))));
+    assert_eq!(*cancelled.lock().unwrap(), vec![1]);
+}
+
+/// The cancel hook runs without holding the dispatcher's lock, so it can use the dispatcher.
+#[test]
+fn cancel_hook_can_use_the_dispatcher() {
+    let slot: Arc<Mutex<Option<Dispatcher<u64, u8>>>> = Arc::new(Mutex::new(None));
+    let hook_slot = slot.clone();
+    let mut dispatcher = Dispatcher::with_cancel_hook(move |_| {
+        if let Some(dispatcher) = hook_slot.lock().unwrap().take() {
+            dispatcher.shutdown(9);
+        }
+    });
+
+    *slot.lock().unwrap() = Some(dispatcher.clone());
+
+    let (cancelled_sender, cancelled) = dispatcher.cancellable_oneshot(1);
+    let (other_sender, other) = dispatcher.cancellable_oneshot(2);
+
+    executor::block_on(async {
+        dispatcher.send((1, cancelled_sender)).await.unwrap();
+        dispatcher.send((2, other_sender)).await.unwrap();
+    });
+
+    drop(cancelled);
+
+    assert!(matches!(other.now_or_never(), Some(Ok(9))));
+}
+
+/// Closing a clone's sink of requests doesn't affect the requests registered through other clones.
+#[test]
+fn closing_a_registration_clone_keeps_pending_requests() {
+    let mut dispatcher: Dispatcher<u64, u8> = Dispatcher::new();
+    let mut registrar = dispatcher.clone();
+    let mut deadline_registrar = dispatcher.clone();
+
+    let (pending_sender, pending) = dispatcher.cancellable_oneshot(1);
+    let (later_sender, later) = dispatcher.cancellable_oneshot(2);
+
+    executor::block_on(async {
+        dispatcher.send((1, pending_sender)).await.unwrap();
+
+        SinkExt::<(u64, Sender<u8>)>::close(&mut registrar)
+            .await
+            .unwrap();
+        SinkExt::<(u64, Sender<u8>, Instant)>::close(&mut deadline_registrar)
+            .await
+            .unwrap();
+
+        dispatcher.send((2, later_sender)).await.unwrap();
+        dispatcher.send((1, 1)).await.unwrap();
+        dispatcher.send((2, 2)).await.unwrap();
+    });
+
+    assert!(matches!(pending.now_or_never(), Some(Ok(1))));
+    assert!(matches!(later.now_or_never(), Some(Ok(2))));
+}
+
+/// Shutting down resolves every pending request with the reason, and the dispatcher rejects any
+/// later requests and responses.
+#[test]
+fn shutdown_resolves_pending_requests_and_rejects_later_ones() {
+    let mut dispatcher: Dispatcher<u64, u8> = Dispatcher::new();
+    let clone = dispatcher.clone();
+
+    let (first_sender, first) = dispatcher.cancellable_oneshot(1);
+    let (second_sender, second) = dispatcher.cancellable_oneshot(2);
+    let deadline = Instant::now() + Duration::from_secs(60);
+
+    executor::block_on(async {
+        dispatcher.send((1, first_sender)).await.unwrap();
+        dispatcher.send((2, second_sender, deadline)).await.unwrap();
+    });
+
+    clone.shutdown(9);
+
+    assert!(matches!(first.now_or_never(), Some(Ok(9))));
+    assert!(matches!(second.now_or_never(), Some(Ok(9))));
+
+    let (late_sender, late) = dispatcher.cancellable_oneshot(3);
+
+    executor::block_on(async {
+        assert!(matches!(
+            dispatcher.send((3, late_sender)).await,
+            Err(DispatcherError::Closed)
+        ));
+        assert!(matches!(
+            dispatcher.send((1, 1)).await,
+            Err(DispatcherError::Closed)
+        ));
+    });
+
+    assert!(matches!(late.now_or_never(), Some(Err(_))));
+}
+
+/// Responses for unknown IDs are counted and ignored by default, or rejected by a strict policy.
+#[test]
+fn responses_for_unknown_ids_follow_the_policy() {
+    let mut lenient: Dispatcher<u64, u8> = Dispatcher::new();
+    let mut strict: Dispatcher<u64, u8> = Dispatcher::new().with_policy(DispatcherPolicy::strict());
+
+    executor::block_on(async {
+        lenient.send((1, 1)).await.unwrap();
+        assert!(matches!(
+            strict.send((1, 1)).await,
+            Err(DispatcherError::UnknownId(1))
+        ));
+    });
+
+    assert_eq!(
+        lenient.ignored_errors(),
+        IgnoredErrors {
+            unknown_ids: 1,
+            ..IgnoredErrors::default()
+        }
+    );
+    assert_eq!(strict.ignored_errors(), IgnoredErrors::default());
+}
+
+/// Registering a duplicate ID is rejected by default, keeping the pending request, or replaces the
+/// pending request if the policy ignores it.
+#[test]
+fn duplicate_ids_follow_the_policy() {
+    let mut strict: Dispatcher<u64, u8> = Dispatcher::new();
+    let mut lenient: Dispatcher<u64, u8> =
+        Dispatcher::new().with_policy(DispatcherPolicy::new().fail_on_duplicate_id(false));
+
+    let (kept_sender, kept) = async_oneshot::oneshot();
+    let (rejected_sender, rejected) = async_oneshot::oneshot();
+    let (replaced_sender, replaced) = async_oneshot::oneshot();
+    let (replacement_sender, replacement) = async_oneshot::oneshot();
+
+    executor::block_on(async {
+        strict.send((1, kept_sender)).await.unwrap();
+        assert!(matches!(
+            strict.send((1, rejected_sender)).await,
+            Err(DispatcherError::DuplicateId(1))
+        ));
+        strict.send((1, 1)).await.unwrap();
+
+        lenient.send((1, replaced_sender)).await.unwrap();
+        lenient.send((1, replacement_sender)).await.unwrap();
+        lenient.send((1, 2)).await.unwrap();
+    });
+
+    assert!(matches!(kept.now_or_never(), Some(Ok(1))));
+    assert!(matches!(rejected.now_or_never(), Some(Err(_))));
+    assert!(matches!(replaced.now_or_never(), Some(Err(_))));
+    assert!(matches!(replacement.now_or_never(), Some(Ok(2))));
+    assert_eq!(strict.ignored_errors(), IgnoredErrors::default());
+    assert_eq!(
+        lenient.ignored_errors(),
+        IgnoredErrors {
+            duplicate_ids: 1,
+            ..IgnoredErrors::default()
+        }
+    );
+}
+
+/// A response for a request whose receiver was dropped is a dropped receiver, while a response
+/// for a cancelled request is for an unknown ID, because cancelling removes the request.
+#[test]
+fn responses_for_dropped_receivers_and_cancelled_requests_follow_the_policy() {
+    let mut lenient: Dispatcher<u64, u8> = Dispatcher::new();
+    let mut strict: Dispatcher<u64, u8> = Dispatcher::new().with_policy(DispatcherPolicy::strict());
+
+    for dispatcher in [&mut lenient, &mut strict] {
+        let (dropped_sender, dropped) = async_oneshot::oneshot();
+        let (cancelled_sender, cancelled) = dispatcher.cancellable_oneshot(2);
+
+        executor::block_on(async {
+            dispatcher.send((1, dropped_sender)).await.unwrap();
+            dispatcher.send((2, cancelled_sender)).await.unwrap();
+        });
+
+        drop(dropped);
+        drop(cancelled);
+    }
+
+    executor::block_on(async {
+        lenient.send((1, 1)).await.unwrap();
+        lenient.send((2, 2)).await.unwrap();
+
+        assert!(matches!(
+            strict.send((1, 1)).await,
+            Err(DispatcherError::ReceiverDropped(1))
+        ));
+        assert!(matches!(
+            strict.send((2, 2)).await,
+            Err(DispatcherError::UnknownId(2))
+        ));
+    });
+
+    assert_eq!(
+        lenient.ignored_errors(),
+        IgnoredErrors {
+            unknown_ids: 1,
+            receivers_dropped: 1,
+            ..IgnoredErrors::default()
+        }
+    );
+    assert_eq!(strict.ignored_errors(), IgnoredErrors::default());
+}
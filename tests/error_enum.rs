@@ -0,0 +1,92 @@
+use {
+    ezrpc::CallError,
+    futures::executor,
+    std::{
+        error::Error as _,
+        fmt::{self, Display, Formatter},
+        num::ParseIntError,
+    },
+};
+
+#[derive(Debug)]
+pub struct Rejected {
+    cause: ParseIntError,
+}
+
+impl Display for Rejected {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        write!(formatter, "Rejected number")
+    }
+}
+
+impl std::error::Error for Rejected {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.cause)
+    }
+}
+
+pub struct Checker;
+
+#[ezrpc::tower(error_enum)]
+impl Checker {
+    pub fn parse(&self, text: String) -> Result<u32, ParseIntError> {
+        text.parse()
+    }
+
+    pub fn check(&self, text: String) -> Result<(), Rejected> {
+        text.parse::<u8>()
+            .map(|_| ())
+            .map_err(|cause| Rejected { cause })
+    }
+}
+
+#[test]
+fn method_errors_are_wrapped_in_their_variants() {
+    let mut service = Service::new(Checker);
+
+    executor::block_on(async move {
+        assert_eq!(service.parse("7".to_owned()).await.ok(), Some(7));
+        assert!(matches!(
+            service.parse("x".to_owned()).await,
+            Err(CallError::Failed(Error::ParseIntError(_)))
+        ));
+        assert_eq!(service.check("7".to_owned()).await.ok(), Some(()));
+        assert!(matches!(
+            service.check("300".to_owned()).await,
+            Err(CallError::Failed(Error::Rejected(_)))
+        ));
+    });
+}
+
+#[test]
+fn error_enum_is_converted_from_the_method_errors() {
+    let parse_error = "x".parse::<u32>().unwrap_err();
+    let rejected = Rejected {
+        cause: parse_error.clone(),
+    };
+
+    assert!(matches!(Error::from(parse_error), Error::ParseIntError(_)));
+    assert!(matches!(Error::from(rejected), Error::Rejected(_)));
+}
+
+#[test]
+fn error_enum_delegates_to_the_wrapped_errors() {
+    let parse_error = "x".parse::<u32>().unwrap_err();
+    let parse_failure = Error::from(parse_error.clone());
+    let rejected = Error::from(Rejected {
+        cause: parse_error.clone(),
+    });
+
+    assert_eq!(parse_failure.to_string(), parse_error.to_string());
+    assert_eq!(
+        format!("{:?}", parse_failure),
+        format!("ParseIntError({:?})", parse_error)
+    );
+    assert!(parse_failure.source().is_none());
+
+    assert_eq!(rejected.to_string(), "Rejected number");
+    assert_eq!(
+        rejected.source().map(ToString::to_string),
+        Some(parse_error.to_string())
+    );
+}
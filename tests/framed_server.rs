@@ -0,0 +1,106 @@
+#![cfg(feature = "json")]
+
+use {
+    ezrpc::{
+        codec::{CodecError, Framed, Json},
+        server::{self, ServeError},
+        RequestId,
+    },
+    futures::{
+        executor, future,
+        io::{AsyncRead, AsyncWrite, Cursor},
+        StreamExt,
+    },
+    std::{
+        io,
+        pin::Pin,
+        task::{Context, Poll},
+    },
+};
+
+/// A transport that reads from a fixed input, and collects everything written to it.
+struct Pipe {
+    input: Cursor<Vec<u8>>,
+    output: Vec<u8>,
+}
+
+impl AsyncRead for Pipe {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        context: &mut Context<'_>,
+        buffer: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.input).poll_read(context, buffer)
+    }
+}
+
+impl AsyncWrite for Pipe {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        context: &mut Context<'_>,
+        buffer: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.output).poll_write(context, buffer)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Encode a frame with a raw JSON `payload`.
+fn frame(id: RequestId, payload: &str) -> Vec<u8> {
+    let length = (8 + payload.len()) as u32;
+
+    [
+        &length.to_be_bytes()[..],
+        &id.to_be_bytes(),
+        payload.as_bytes(),
+    ]
+    .concat()
+}
+
+/// A response envelope sent by the server.
+type Response = (RequestId, Result<u32, ()>);
+
+/// Serve a doubling service over a transport that receives the `input` bytes, and return the
+/// result of the server together with the responses it sent.
+fn serve(input: Vec<u8>) -> (Result<(), ServeError<CodecError, ()>>, Vec<Response>) {
+    let pipe = Pipe {
+        input: Cursor::new(input),
+        output: Vec::new(),
+    };
+    let mut framed: Framed<_, _, u32, Result<u32, ()>> = Framed::new(pipe, Json);
+    let service = tower::service_fn(|value: u32| future::ready(Ok::<_, ()>(value * 2)));
+    let result = executor::block_on(server::serve(service, &mut framed));
+    let output = framed.into_inner().output;
+    let responses: Framed<_, _, Result<u32, ()>, ()> = Framed::new(Cursor::new(output), Json);
+    let responses = executor::block_on(responses.map(Result::unwrap).collect());
+
+    (result, responses)
+}
+
+#[test]
+fn undecodable_request_is_skipped() {
+    let input = [frame(1, "\"one\""), frame(2, "2"), frame(3, "3")].concat();
+    let (result, responses) = serve(input);
+
+    assert!(result.is_ok());
+    assert_eq!(responses, vec![(2, Ok(4)), (3, Ok(6))]);
+}
+
+#[test]
+fn failure_that_ends_the_transport_is_returned_after_the_calls_finish() {
+    let input = [frame(1, "1"), u32::MAX.to_be_bytes().to_vec()].concat();
+    let (result, responses) = serve(input);
+
+    assert!(matches!(
+        result,
+        Err(ServeError::Transport(CodecError::FrameTooLarge { .. }))
+    ));
+    assert_eq!(responses, vec![(1, Ok(2))]);
+}
@@ -0,0 +1,81 @@
+use {
+    futures::executor,
+    std::collections::HashMap,
+    tower::{Service as _, ServiceExt as _},
+};
+
+pub trait Store {
+    fn load(&self, key: &str) -> Option<u32>;
+}
+
+impl Store for HashMap<String, u32> {
+    fn load(&self, key: &str) -> Option<u32> {
+        self.get(key).copied()
+    }
+}
+
+pub struct Cache<S> {
+    store: S,
+}
+
+#[ezrpc::tower(module = cache_rpc)]
+impl<S> Cache<S>
+where
+    S: Store + Send + Sync + 'static,
+{
+    pub fn load(&self, key: String) -> Option<u32> {
+        self.store.load(&key)
+    }
+}
+
+pub struct Stack<T> {
+    items: Vec<T>,
+}
+
+#[ezrpc::tower(module = stack_rpc)]
+impl<T: Send + Sync + 'static> Stack<T> {
+    pub fn push(&mut self, item: T) -> usize {
+        self.items.push(item);
+        self.items.len()
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.items.pop()
+    }
+}
+
+#[test]
+fn generic_parameter_only_used_by_the_instance() {
+    let mut store = HashMap::new();
+
+    store.insert("one".to_owned(), 1);
+
+    let mut service = cache_rpc::Service::new(Cache { store });
+
+    executor::block_on(async move {
+        assert_eq!(service.load("one".to_owned()).await, Some(1));
+        assert_eq!(service.load("two".to_owned()).await, None);
+    });
+}
+
+#[test]
+fn generic_parameter_used_by_the_request_and_response() {
+    let mut service = stack_rpc::Service::new(Stack { items: vec![] });
+
+    executor::block_on(async move {
+        let response = service
+            .ready()
+            .await
+            .unwrap()
+            .call(stack_rpc::Request::Push {
+                item: "first".to_owned(),
+            })
+            .await;
+
+        assert!(matches!(response, Ok(stack_rpc::Response::Push(1))));
+        assert_eq!(service.push("second".to_owned()).await, Ok(2));
+        assert_eq!(service.pop().await, Ok(Some("second".to_owned())));
+        assert_eq!(service.pop().await, Ok(Some("first".to_owned())));
+        assert_eq!(service.pop().await, Ok(None));
+    });
+}
@@ -0,0 +1,25 @@
+use futures::executor;
+
+#[ezrpc::tower]
+pub trait Echo<T: Send + 'static> {
+    async fn echo(&self, value: T) -> Result<T, String>;
+}
+
+pub struct Local;
+
+impl<T: Send + 'static> Echo<T> for Local {
+    async fn echo(&self, value: T) -> Result<T, String> {
+        Ok(value)
+    }
+}
+
+#[test]
+fn generic_trait_is_served_and_implemented_by_the_client() {
+    let mut service: Service<Local, u8> = Service::new(Local);
+    let client = Client::new(Service::<_, String>::new(Local));
+
+    executor::block_on(async move {
+        assert_eq!(service.echo(7).await, Ok(7));
+        assert_eq!(client.echo("hi".to_owned()).await, Ok("hi".to_owned()));
+    });
+}
@@ -0,0 +1,61 @@
+use {
+    futures::executor,
+    tower::{Service as _, ServiceExt as _},
+};
+
+pub struct Numbers {
+    values: Vec<u32>,
+}
+
+#[ezrpc::tower(skip_private)]
+impl Numbers {
+    #[ezrpc(rename = "Total")]
+    pub fn sum(&self) -> u32 {
+        self.values.iter().sum()
+    }
+
+    pub fn count(&self) -> u32 {
+        self.len()
+    }
+
+    #[ezrpc(skip)]
+    pub fn first(&self) -> Option<u32> {
+        self.values.first().copied()
+    }
+
+    fn len(&self) -> u32 {
+        self.values.len() as u32
+    }
+}
+
+#[test]
+fn skipped_methods_have_no_request_variants() {
+    // The match is only exhaustive if the skipped and private methods have no variants.
+    let method = match Request::Total {
+        Request::Total => "sum",
+        Request::Count => "count",
+    };
+
+    assert_eq!(method, "sum");
+}
+
+#[test]
+fn skipped_methods_stay_callable_on_the_instance() {
+    let numbers = Numbers { values: vec![2, 3] };
+
+    assert_eq!(numbers.first(), Some(2));
+    assert_eq!(numbers.len(), 2);
+}
+
+#[test]
+fn renamed_method_is_called_through_its_variant() {
+    let mut service = Service::new(Numbers { values: vec![2, 3] });
+
+    executor::block_on(async move {
+        let response = service.ready().await.unwrap().call(Request::Total).await;
+
+        assert_eq!(response, Ok(5));
+        assert_eq!(service.sum().await, 5);
+        assert_eq!(service.count().await, 2);
+    });
+}
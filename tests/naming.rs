@@ -0,0 +1,77 @@
+use {
+    futures::executor,
+    tower::{Service as _, ServiceExt as _},
+};
+
+mod services {
+    pub struct Calculator;
+
+    #[ezrpc::tower(
+        request = CalculatorRequest,
+        response = CalculatorResponse,
+        service = CalculatorService
+    )]
+    impl Calculator {
+        pub fn add(&self, a: u32, b: u32) -> u32 {
+            a + b
+        }
+
+        pub fn is_zero(&self, number: u32) -> bool {
+            number == 0
+        }
+    }
+
+    pub struct Greeter;
+
+    #[ezrpc::tower(module = greeter_rpc)]
+    impl Greeter {
+        pub fn greet(&self, name: String) -> String {
+            format!("Hello, {}!", name)
+        }
+
+        pub fn count(&self, name: String) -> usize {
+            name.len()
+        }
+    }
+}
+
+use self::services::{greeter_rpc, CalculatorRequest, CalculatorResponse, CalculatorService};
+
+#[test]
+fn renamed_types_are_generated() {
+    let mut service = CalculatorService::new(services::Calculator);
+
+    executor::block_on(async move {
+        let response = service
+            .ready()
+            .await
+            .unwrap()
+            .call(CalculatorRequest::Add { a: 1, b: 2 })
+            .await;
+
+        assert!(matches!(response, Ok(CalculatorResponse::Add(3))));
+        assert_eq!(service.is_zero(4).await, Ok(false));
+    });
+}
+
+#[test]
+fn generated_types_are_placed_in_the_module() {
+    let mut service = greeter_rpc::Service::new(services::Greeter);
+
+    executor::block_on(async move {
+        let response = service
+            .ready()
+            .await
+            .unwrap()
+            .call(greeter_rpc::Request::Count {
+                name: "world".to_owned(),
+            })
+            .await;
+
+        assert!(matches!(response, Ok(greeter_rpc::Response::Count(5))));
+        assert_eq!(
+            service.greet("world".to_owned()).await,
+            Ok("Hello, world!".to_owned())
+        );
+    });
+}
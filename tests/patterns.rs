@@ -0,0 +1,101 @@
+use {
+    futures::executor,
+    tower::{Service as _, ServiceExt as _},
+};
+
+pub struct Size {
+    pub width: u32,
+    pub height: u32,
+}
+
+pub struct Geometry;
+
+#[ezrpc::tower]
+impl Geometry {
+    pub fn area(&self, (width, height): (u32, u32)) -> u32 {
+        width * height
+    }
+
+    pub fn perimeter(&self, Size { width, height }: Size, _: bool) -> u32 {
+        2 * (width + height)
+    }
+
+    pub fn grow(&self, mut size: u32, amount: u32) -> u32 {
+        size += amount;
+        size
+    }
+
+    pub fn scale(&self, (factor, offset): (u32, u32), arg0: u32) -> u32 {
+        factor * arg0 + offset
+    }
+}
+
+#[test]
+fn patterns_are_sent_as_synthetic_fields() {
+    let mut service = Service::new(Geometry);
+
+    executor::block_on(async move {
+        let area = service
+            .ready()
+            .await
+            .unwrap()
+            .call(Request::Area { arg0: (2, 3) })
+            .await;
+        let perimeter = service
+            .ready()
+            .await
+            .unwrap()
+            .call(Request::Perimeter {
+                arg0: Size {
+                    width: 2,
+                    height: 3,
+                },
+                arg1: true,
+            })
+            .await;
+
+        assert_eq!(area, Ok(6));
+        assert_eq!(perimeter, Ok(10));
+    });
+}
+
+#[test]
+fn synthetic_fields_do_not_clash_with_parameter_names() {
+    let mut service = Service::new(Geometry);
+
+    executor::block_on(async move {
+        let scaled = service
+            .ready()
+            .await
+            .unwrap()
+            .call(Request::Scale {
+                arg0_: (2, 1),
+                arg0: 3,
+            })
+            .await;
+
+        assert_eq!(scaled, Ok(7));
+    });
+}
+
+#[test]
+fn helpers_take_the_original_parameters() {
+    let mut service = Service::new(Geometry);
+
+    executor::block_on(async move {
+        assert_eq!(service.grow(2, 3).await, 5);
+        assert_eq!(service.area((4, 5)).await, 20);
+        assert_eq!(
+            service
+                .perimeter(
+                    Size {
+                        width: 1,
+                        height: 1
+                    },
+                    false
+                )
+                .await,
+            4
+        );
+    });
+}
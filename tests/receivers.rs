@@ -0,0 +1,65 @@
+use {futures::executor, std::sync::Arc};
+
+#[derive(Clone)]
+pub struct Accumulator {
+    total: u32,
+}
+
+#[ezrpc::tower(module = accumulator_rpc)]
+impl Accumulator {
+    pub fn accumulate(mut self, amount: u32) -> u32 {
+        self.total += amount;
+        self.total
+    }
+}
+
+pub struct Registry {
+    name: String,
+}
+
+#[ezrpc::tower(module = registry_rpc)]
+impl Registry {
+    pub fn name(self: Arc<Self>) -> String {
+        self.name.clone()
+    }
+
+    pub fn handles(self: Arc<Self>) -> usize {
+        Arc::strong_count(&self)
+    }
+
+    pub fn describe(&self, prefix: String) -> String {
+        format!("{}{}", prefix, self.name)
+    }
+}
+
+#[test]
+fn owned_receiver_is_called_on_a_clone() {
+    let mut service = accumulator_rpc::Service::new(Accumulator { total: 10 });
+
+    executor::block_on(async move {
+        assert_eq!(service.accumulate(1).await, 11);
+        assert_eq!(service.accumulate(2).await, 12);
+        assert_eq!(service.inner().total, 10);
+    });
+}
+
+#[test]
+fn arc_receiver_is_called_on_the_shared_instance() {
+    let instance = Arc::new(Registry {
+        name: "registry".to_owned(),
+    });
+    let mut service = registry_rpc::Service::from_shared(instance.clone());
+
+    executor::block_on(async move {
+        assert_eq!(service.name().await, Ok("registry".to_owned()));
+        assert_eq!(
+            service.describe("the ".to_owned()).await,
+            Ok("the registry".to_owned())
+        );
+
+        // The test, the service and the call each hold a handle to the instance.
+        assert_eq!(service.handles().await, Ok(3));
+    });
+
+    assert_eq!(Arc::strong_count(&instance), 1);
+}
@@ -0,0 +1,218 @@
+use {
+    ezrpc::{CallError, RequestId},
+    futures::{
+        channel::mpsc::{self, UnboundedReceiver, UnboundedSender},
+        executor, StreamExt,
+    },
+};
+
+/// A response received by a `RemoteClient`.
+type Received<Response, Error> = Result<(RequestId, Result<Response, Error>), ()>;
+
+/// Respond to every request received by a `RemoteClient` with the result of `respond`.
+async fn respond_with<Request, Response, Error>(
+    mut requests: UnboundedReceiver<(RequestId, Request)>,
+    responses: UnboundedSender<Received<Response, Error>>,
+    respond: impl Fn() -> Result<Response, Error>,
+) {
+    while let Some((id, _)) = requests.next().await {
+        let _ = responses.unbounded_send(Ok((id, respond())));
+    }
+}
+
+mod shared {
+    use super::*;
+
+    #[derive(Debug, Eq, PartialEq)]
+    pub struct Overflow;
+
+    pub struct Calculator;
+
+    #[ezrpc::tower(remote_client)]
+    impl Calculator {
+        pub fn add(&self, a: u8, b: u8) -> Result<u8, Overflow> {
+            a.checked_add(b).ok_or(Overflow)
+        }
+
+        pub fn subtract(&self, a: u8, b: u8) -> Result<u8, Overflow> {
+            a.checked_sub(b).ok_or(Overflow)
+        }
+    }
+
+    #[test]
+    fn helpers_return_the_method_results() {
+        let mut service = Service::new(Calculator);
+
+        executor::block_on(async move {
+            assert_eq!(service.add(1, 2).await, Ok(3));
+            assert_eq!(service.add(255, 1).await, Err(Overflow));
+            assert_eq!(service.subtract(2, 1).await, Ok(1));
+            assert_eq!(service.subtract(1, 2).await, Err(Overflow));
+        });
+    }
+
+    #[test]
+    fn remote_helpers_report_failures_as_call_errors() {
+        let (requests, request_receiver) = mpsc::unbounded();
+        let (response_sender, responses) = mpsc::unbounded();
+        let (mut client, task) = RemoteClient::new(requests, responses);
+        let server = respond_with(request_receiver, response_sender, || Err(Overflow));
+
+        executor::block_on(async move {
+            let call = async move {
+                assert_eq!(client.add(1, 2).await, Err(CallError::Failed(Overflow)));
+            };
+
+            futures::join!(call, task, server);
+        });
+    }
+}
+
+mod disjoint_with_shared_error {
+    use super::*;
+
+    #[derive(Debug, Eq, PartialEq)]
+    pub struct Invalid;
+
+    pub struct Parser;
+
+    #[ezrpc::tower(remote_client)]
+    impl Parser {
+        pub fn number(&self, text: String) -> Result<u8, Invalid> {
+            text.parse().map_err(|_| Invalid)
+        }
+
+        pub fn words(&self, text: String) -> Result<Vec<String>, Invalid> {
+            if text.is_empty() {
+                Err(Invalid)
+            } else {
+                Ok(text.split(' ').map(str::to_owned).collect())
+            }
+        }
+    }
+
+    #[test]
+    fn helpers_extract_the_outputs() {
+        let mut service = Service::new(Parser);
+
+        executor::block_on(async move {
+            assert_eq!(service.number("7".to_owned()).await, Ok(7));
+            assert_eq!(
+                service.number("x".to_owned()).await,
+                Err(CallError::Failed(Invalid))
+            );
+            assert_eq!(
+                service.words("a b".to_owned()).await,
+                Ok(vec!["a".to_owned(), "b".to_owned()])
+            );
+            assert_eq!(
+                service.words(String::new()).await,
+                Err(CallError::Failed(Invalid))
+            );
+        });
+    }
+
+    #[test]
+    fn remote_helpers_reject_the_output_of_another_method() {
+        let (requests, request_receiver) = mpsc::unbounded();
+        let (response_sender, responses) = mpsc::unbounded();
+        let (mut client, task) = RemoteClient::new(requests, responses);
+        let server = respond_with(request_receiver, response_sender, || {
+            Ok(Response::Words(vec![]))
+        });
+
+        executor::block_on(async move {
+            let call = async move {
+                assert_eq!(
+                    client.number("7".to_owned()).await,
+                    Err(CallError::UnexpectedResponse)
+                );
+            };
+
+            futures::join!(call, task, server);
+        });
+    }
+}
+
+mod fully_disjoint {
+    use {super::*, std::num::ParseIntError};
+
+    #[derive(Debug, Eq, PartialEq)]
+    pub struct TooLong;
+
+    pub struct Parser;
+
+    #[ezrpc::tower(remote_client)]
+    impl Parser {
+        pub fn number(&self, text: String) -> Result<u8, ParseIntError> {
+            text.parse()
+        }
+
+        pub fn length(&self, text: String) -> Result<usize, TooLong> {
+            if text.len() > 10 {
+                Err(TooLong)
+            } else {
+                Ok(text.len())
+            }
+        }
+    }
+
+    #[test]
+    fn helpers_extract_the_method_results() {
+        let mut service = Service::new(Parser);
+        let parse_error = "x".parse::<u8>().unwrap_err();
+
+        executor::block_on(async move {
+            assert_eq!(service.number("7".to_owned()).await, Ok(7));
+            assert_eq!(
+                service.number("x".to_owned()).await,
+                Err(CallError::Failed(parse_error))
+            );
+            assert_eq!(service.length("abc".to_owned()).await, Ok(3));
+            assert_eq!(
+                service.length("a".repeat(11)).await,
+                Err(CallError::Failed(TooLong))
+            );
+        });
+    }
+
+    #[test]
+    fn remote_helpers_reject_the_output_of_another_method() {
+        let (requests, request_receiver) = mpsc::unbounded();
+        let (response_sender, responses) = mpsc::unbounded();
+        let (mut client, task) = RemoteClient::new(requests, responses);
+        let server = respond_with(request_receiver, response_sender, || {
+            Ok(Response::Length(Ok(0)))
+        });
+
+        executor::block_on(async move {
+            let call = async move {
+                assert_eq!(
+                    client.number("7".to_owned()).await,
+                    Err(CallError::UnexpectedResponse)
+                );
+            };
+
+            futures::join!(call, task, server);
+        });
+    }
+
+    #[test]
+    fn remote_helpers_report_a_failed_service_as_unavailable() {
+        let (requests, request_receiver) = mpsc::unbounded();
+        let (response_sender, responses) = mpsc::unbounded();
+        let (mut client, task) = RemoteClient::new(requests, responses);
+        let server = respond_with(request_receiver, response_sender, || Err(()));
+
+        executor::block_on(async move {
+            let call = async move {
+                assert_eq!(
+                    client.length("abc".to_owned()).await,
+                    Err(CallError::Unavailable)
+                );
+            };
+
+            futures::join!(call, task, server);
+        });
+    }
+}
@@ -0,0 +1,46 @@
+use {ezrpc::CallError, futures::executor};
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct Invalid;
+
+pub type ParseResult<T> = Result<T, Invalid>;
+
+pub type Parsed<T> = Result<T, Invalid>;
+
+pub struct Parser;
+
+#[ezrpc::tower]
+#[ezrpc(error = Invalid)]
+impl Parser {
+    pub fn byte(&self, text: String) -> ParseResult<u8> {
+        text.parse().map_err(|_| Invalid)
+    }
+
+    pub fn bytes(&self, text: String) -> Vec<u8> {
+        text.into_bytes()
+    }
+
+    #[ezrpc(error = Invalid)]
+    pub fn word(&self, text: String) -> Parsed<u16> {
+        text.parse().map_err(|_| Invalid)
+    }
+}
+
+#[test]
+fn result_aliases_use_the_declared_error() {
+    let mut service = Service::new(Parser);
+
+    executor::block_on(async move {
+        assert_eq!(service.byte("7".to_owned()).await, Ok(7));
+        assert_eq!(
+            service.byte("x".to_owned()).await,
+            Err(CallError::Failed(Invalid))
+        );
+        assert_eq!(service.bytes("ab".to_owned()).await, Ok(vec![b'a', b'b']));
+        assert_eq!(service.word("300".to_owned()).await, Ok(300));
+        assert_eq!(
+            service.word("x".to_owned()).await,
+            Err(CallError::Failed(Invalid))
+        );
+    });
+}
@@ -0,0 +1,62 @@
+use {
+    futures::{executor, future},
+    std::{cell::Cell, rc::Rc, thread},
+    tower::{Service as _, ServiceExt as _},
+};
+
+pub struct Counter {
+    count: u32,
+}
+
+#[ezrpc::tower(module = counter_rpc)]
+impl Counter {
+    pub async fn increment(&mut self) -> u32 {
+        future::ready(()).await;
+        self.count += 1;
+        self.count
+    }
+}
+
+pub struct LocalCounter {
+    count: Rc<Cell<u32>>,
+}
+
+#[ezrpc::tower(local, module = local_counter_rpc)]
+impl LocalCounter {
+    pub async fn increment(&self) -> u32 {
+        let count = self.count.clone();
+
+        future::ready(()).await;
+        count.set(count.get() + 1);
+        count.get()
+    }
+}
+
+#[test]
+fn responses_can_be_awaited_on_another_thread() {
+    let mut service = counter_rpc::Service::new(Counter { count: 0 });
+    let response = executor::block_on(service.ready())
+        .unwrap()
+        .call(counter_rpc::Request::Increment);
+
+    let result = thread::spawn(move || executor::block_on(response))
+        .join()
+        .unwrap();
+
+    assert_eq!(result, Ok(1));
+}
+
+#[test]
+fn local_services_accept_futures_that_are_not_send() {
+    let count = Rc::new(Cell::new(0));
+    let mut service = local_counter_rpc::Service::new(LocalCounter {
+        count: count.clone(),
+    });
+
+    executor::block_on(async move {
+        assert_eq!(service.increment().await, 1);
+        assert_eq!(service.increment().await, 2);
+    });
+
+    assert_eq!(count.get(), 2);
+}
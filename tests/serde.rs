@@ -0,0 +1,50 @@
+#![cfg(feature = "json")]
+
+use serde_json::json;
+
+/// A parameter type that can't be serialized.
+pub struct Handle(u8);
+
+pub struct Local;
+
+#[ezrpc::tower(module = local_rpc)]
+impl Local {
+    pub fn unwrap(&self, handle: Handle) -> u8 {
+        handle.0
+    }
+}
+
+pub struct Calculator;
+
+#[ezrpc::tower(module = calculator_rpc, tagging = internal)]
+impl Calculator {
+    pub fn add(&self, a: u8, b: u8) -> u8 {
+        a + b
+    }
+
+    pub fn negate(&self, a: i8) -> i8 {
+        -a
+    }
+}
+
+#[test]
+fn services_without_serde_accept_any_types() {
+    let request = local_rpc::Request::Unwrap { handle: Handle(1) };
+
+    assert!(matches!(request, local_rpc::Request::Unwrap { .. }));
+}
+
+#[test]
+fn internal_tagging_only_applies_to_requests() {
+    let request = calculator_rpc::Request::Add { a: 1, b: 2 };
+    let response = calculator_rpc::Response::Negate(-1);
+
+    assert_eq!(
+        serde_json::to_value(request).unwrap(),
+        json!({ "method": "Add", "a": 1, "b": 2 })
+    );
+    assert_eq!(
+        serde_json::to_value(response).unwrap(),
+        json!({ "method": "Negate", "output": -1 })
+    );
+}
@@ -0,0 +1,256 @@
+mod common;
+
+use {
+    self::common::ChannelTransport,
+    ezrpc::{
+        server::{ServeError, Server},
+        RequestId,
+    },
+    futures::{
+        channel::{
+            mpsc::{UnboundedReceiver, UnboundedSender},
+            oneshot,
+        },
+        executor::LocalPool,
+        task::LocalSpawnExt,
+        FutureExt, StreamExt,
+    },
+    std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+        task::{Context, Poll},
+    },
+    tower::Service,
+};
+
+/// A request that is handled once its gate is opened, and responds with its value.
+type GatedRequest = (u32, oneshot::Receiver<()>);
+
+/// A response envelope sent by the server.
+type Response = (RequestId, Result<u32, ()>);
+
+/// The result of a finished server, without the transport's error.
+type ServeResult = Result<(), ServeError<(), ()>>;
+
+/// A service that fails to become ready once it has started a number of calls.
+struct ReadyLimit<S> {
+    service: S,
+    remaining_calls: usize,
+}
+
+impl<S, Request> Service<Request> for ReadyLimit<S>
+where
+    S: Service<Request, Error = ()>,
+{
+    type Response = S::Response;
+    type Error = ();
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, context: &mut Context<'_>) -> Poll<Result<(), ()>> {
+        if self.remaining_calls == 0 {
+            Poll::Ready(Err(()))
+        } else {
+            self.service.poll_ready(context)
+        }
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        self.remaining_calls -= 1;
+        self.service.call(request)
+    }
+}
+
+/// A server running on a [`LocalPool`], with its gated service and the peer side of its
+/// transport.
+struct TestServer {
+    pool: LocalPool,
+    requests: UnboundedSender<(RequestId, GatedRequest)>,
+    responses: UnboundedReceiver<Response>,
+    gates: Vec<(RequestId, oneshot::Sender<()>)>,
+    started_calls: Rc<Cell<usize>>,
+    result: Rc<RefCell<Option<ServeResult>>>,
+}
+
+impl TestServer {
+    /// Start a server with the `concurrency_limit`.
+    fn start(concurrency_limit: usize) -> Self {
+        Self::start_with_ready_limit(concurrency_limit, usize::MAX)
+    }
+
+    /// Start a server with the `concurrency_limit`, whose service fails to become ready after
+    /// starting `ready_limit` calls.
+    fn start_with_ready_limit(concurrency_limit: usize, ready_limit: usize) -> Self {
+        let (transport, requests, responses) = ChannelTransport::new();
+        let started_calls = Rc::new(Cell::new(0));
+        let service_started_calls = started_calls.clone();
+        let service = tower::service_fn(move |(value, gate): GatedRequest| {
+            service_started_calls.set(service_started_calls.get() + 1);
+
+            gate.map(move |_| Ok::<_, ()>(value))
+        });
+        let service = ReadyLimit {
+            service,
+            remaining_calls: ready_limit,
+        };
+        let result = Rc::new(RefCell::new(None));
+        let server_result = result.clone();
+        let server = Server::new(service)
+            .with_concurrency_limit(concurrency_limit)
+            .serve(transport)
+            .map(move |result| {
+                *server_result.borrow_mut() = Some(result.map_err(|error| match error {
+                    ServeError::Transport(_) => ServeError::Transport(()),
+                    ServeError::Service(error) => ServeError::Service(error),
+                }))
+            });
+        let pool = LocalPool::new();
+
+        pool.spawner().spawn_local(server).unwrap();
+
+        TestServer {
+            pool,
+            requests,
+            responses,
+            gates: Vec::new(),
+            started_calls,
+            result,
+        }
+    }
+
+    /// Send a request with the `id` and `value` to the server.
+    fn send(&mut self, id: RequestId, value: u32) {
+        let (gate, gated) = oneshot::channel();
+
+        self.gates.push((id, gate));
+        self.requests.unbounded_send((id, (value, gated))).unwrap();
+        self.pool.run_until_stalled();
+    }
+
+    /// Let the call for the request with the `id` finish.
+    fn open(&mut self, id: RequestId) {
+        let index = self
+            .gates
+            .iter()
+            .position(|(gate_id, _)| *gate_id == id)
+            .expect("Request was sent");
+        let (_, gate) = self.gates.remove(index);
+
+        gate.send(()).unwrap();
+        self.pool.run_until_stalled();
+    }
+
+    /// Stop sending requests to the server.
+    fn close_requests(&mut self) {
+        self.requests.close_channel();
+        self.pool.run_until_stalled();
+    }
+
+    /// Retrieve the responses sent by the server so far, or [`None`] if the server closed the
+    /// transport and all responses were received.
+    fn received(&mut self) -> Option<Vec<Response>> {
+        let mut responses = Vec::new();
+
+        loop {
+            match self.responses.next().now_or_never() {
+                Some(Some(response)) => responses.push(response),
+                Some(None) if responses.is_empty() => return None,
+                Some(None) | None => return Some(responses),
+            }
+        }
+    }
+
+    /// Check if the server has finished, and if it succeeded.
+    fn result(&self) -> Option<ServeResult> {
+        *self.result.borrow()
+    }
+}
+
+#[test]
+fn responses_are_sent_as_calls_finish_with_their_ids() {
+    let mut server = TestServer::start(8);
+
+    server.send(10, 100);
+    server.send(11, 110);
+    server.send(12, 120);
+
+    assert_eq!(server.started_calls.get(), 3);
+    assert_eq!(server.received(), Some(vec![]));
+
+    server.open(12);
+    assert_eq!(server.received(), Some(vec![(12, Ok(120))]));
+
+    server.open(10);
+    server.open(11);
+    assert_eq!(server.received(), Some(vec![(10, Ok(100)), (11, Ok(110))]));
+    assert_eq!(server.result(), None);
+}
+
+#[test]
+fn no_more_requests_are_read_while_the_concurrency_limit_is_reached() {
+    let mut server = TestServer::start(2);
+
+    for id in 0..4 {
+        server.send(id, id as u32);
+    }
+
+    assert_eq!(server.started_calls.get(), 2);
+
+    server.open(1);
+
+    assert_eq!(server.received(), Some(vec![(1, Ok(1))]));
+    assert_eq!(server.started_calls.get(), 3);
+
+    server.open(3);
+
+    assert_eq!(server.received(), Some(vec![]));
+    assert_eq!(server.started_calls.get(), 3);
+
+    server.open(0);
+
+    assert_eq!(server.started_calls.get(), 4);
+    assert_eq!(server.received(), Some(vec![(0, Ok(0)), (3, Ok(3))]));
+
+    server.open(2);
+
+    assert_eq!(server.received(), Some(vec![(2, Ok(2))]));
+}
+
+#[test]
+fn in_flight_calls_are_finished_after_the_peer_stops_sending_requests() {
+    let mut server = TestServer::start(8);
+
+    server.send(1, 1);
+    server.send(2, 2);
+    server.close_requests();
+
+    assert_eq!(server.result(), None);
+    assert_eq!(server.received(), Some(vec![]));
+
+    server.open(2);
+
+    assert_eq!(server.result(), None);
+    assert_eq!(server.received(), Some(vec![(2, Ok(2))]));
+
+    server.open(1);
+
+    assert_eq!(server.result(), Some(Ok(())));
+    assert_eq!(server.received(), Some(vec![(1, Ok(1))]));
+    assert_eq!(server.received(), None);
+}
+
+#[test]
+fn service_failure_is_reported_after_in_flight_calls_finish() {
+    let mut server = TestServer::start_with_ready_limit(8, 1);
+
+    server.send(1, 1);
+    server.send(2, 2);
+
+    assert_eq!(server.started_calls.get(), 1);
+    assert_eq!(server.result(), None);
+
+    server.open(1);
+
+    assert_eq!(server.result(), Some(Err(ServeError::Service(()))));
+    assert_eq!(server.received(), Some(vec![(1, Ok(1))]));
+    assert_eq!(server.received(), None);
+}
@@ -0,0 +1,9 @@
+#[test]
+fn invalid_services_are_rejected() {
+    let tests = trybuild::TestCases::new();
+
+    tests.compile_fail("tests/ui/*.rs");
+
+    #[cfg(feature = "serde")]
+    tests.compile_fail("tests/ui/serde/*.rs");
+}
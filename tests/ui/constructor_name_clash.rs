@@ -0,0 +1,10 @@
+pub struct Example;
+
+#[ezrpc::tower]
+impl Example {
+    pub fn inner(&self) -> u32 {
+        1
+    }
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+use std::io;
+
+pub struct Example;
+
+#[ezrpc::tower(error_enum)]
+impl Example {
+    pub fn read(&self) -> Result<u8, io::Error> {
+        Ok(0)
+    }
+
+    pub fn write(&self, _byte: u8) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+}
+
+fn main() {
+    let _ = io::stdout();
+}
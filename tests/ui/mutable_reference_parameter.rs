@@ -0,0 +1,10 @@
+pub struct Example;
+
+#[ezrpc::tower]
+impl Example {
+    pub fn increment(&self, value: &mut u32) {
+        *value += 1;
+    }
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+pub struct Example;
+
+#[ezrpc::tower(concurrency = actor, remote_client)]
+impl Example {
+    pub fn new(&mut self) -> u32 {
+        1
+    }
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+pub struct Example;
+
+#[ezrpc::tower]
+impl Example {
+    pub fn first(&self) -> u32 {
+        1
+    }
+
+    #[ezrpc(rename = "First")]
+    pub fn second(&self) -> u32 {
+        2
+    }
+}
+
+fn main() {}
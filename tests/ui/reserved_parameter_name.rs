@@ -0,0 +1,10 @@
+pub struct Example;
+
+#[ezrpc::tower]
+impl Example {
+    pub fn echo(&self, service: u32) -> u32 {
+        service
+    }
+}
+
+fn main() {}
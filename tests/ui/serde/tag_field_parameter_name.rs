@@ -0,0 +1,10 @@
+pub struct Example;
+
+#[ezrpc::tower(tagging = internal)]
+impl Example {
+    pub fn call(&self, method: String) -> String {
+        method
+    }
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+#[ezrpc::tower]
+pub trait Example {
+    async fn echo(&self, value: u32) -> u32;
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+pub struct Example;
+
+#[ezrpc::tower]
+impl Example {
+    pub fn echo(self: Box<Self>, value: u32) -> u32 {
+        value
+    }
+}
+
+fn main() {}